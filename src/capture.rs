@@ -0,0 +1,306 @@
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Ft260Error, Ft260Result};
+
+mod pcapng;
+
+/// File format of `Capture`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Text of one line per report.
+    ///
+    /// The file begins with a comment line `# ft260hid capture 1, started at <UNIX time>`,
+    /// and each report follows as a line of fields separated by a space:
+    ///
+    /// ```text
+    /// <seconds> <interface> <direction> <type> <report ID> <payload>
+    /// 0.000512 0 > feature a1 220190
+    /// 0.001035 0 < input d0 02a55a000000
+    /// ```
+    ///
+    /// - `seconds`: monotonic time since the capture started, with 6 decimal places
+    /// - `interface`: HID interface number given to `Device::start_capture`
+    /// - `direction`: `>` to the device or `<` from the device
+    /// - `type`: `input`, `output` or `feature`
+    /// - `report ID`: 2 hex digits
+    /// - `payload`: hex digits of the bytes following the ID, or `-` if there are none
+    ///
+    /// A feature report from the device is the one returned by Get Feature.
+    /// Lines beginning with `#` are comments.
+    Text,
+    /// [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html) of link type
+    /// `LINKTYPE_USB_LINUX_MMAPPED` (220), which Wireshark dissects as USB HID.
+    ///
+    /// Input and output reports are interrupt transfers, and feature reports are
+    /// `SET_REPORT` and `GET_REPORT` control transfers to the interface.
+    /// Bus, device address and endpoints are nominal, as they are not known to the library.
+    Pcapng,
+}
+
+/// Direction of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the host to the device
+    ToDevice,
+    /// From the device to the host
+    FromDevice,
+}
+
+/// HID report type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportType {
+    Input,
+    Output,
+    Feature,
+}
+
+/// Report captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Monotonic time since the capture started
+    pub timestamp: Duration,
+    /// HID interface number
+    pub interface: u8,
+    pub direction: Direction,
+    pub report_type: ReportType,
+    /// Report beginning with its ID
+    pub data: Vec<u8>,
+}
+
+impl Entry {
+    /// Line of `Format::Text`, without the line break
+    pub fn to_line(&self) -> String {
+        let direction = match self.direction {
+            Direction::ToDevice => '>',
+            Direction::FromDevice => '<',
+        };
+        let report_type = match self.report_type {
+            ReportType::Input => "input",
+            ReportType::Output => "output",
+            ReportType::Feature => "feature",
+        };
+        let mut line = format!(
+            "{}.{:06} {} {} {} {:02x} ",
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.interface,
+            direction,
+            report_type,
+            self.data.first().copied().unwrap_or_default()
+        );
+        match self.data.get(1..) {
+            Some(payload) if !payload.is_empty() => {
+                for b in payload {
+                    let _ = write!(line, "{:02x}", b);
+                }
+            }
+            _ => line.push('-'),
+        }
+        line
+    }
+
+    /// Parse a line of `Format::Text`, which is `None` for a comment or a blank line
+    pub fn from_line(line: &str) -> Ft260Result<Option<Self>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let invalid = || invalid_data(format!("invalid capture line `{}`", line));
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [seconds, interface, direction, report_type, report_id, payload] = fields[..] else {
+            return Err(invalid());
+        };
+        let (secs, fraction) = seconds.split_once('.').ok_or_else(invalid)?;
+        // fraction of a second up to 6 digits, scaled to microseconds by the digit count
+        if fraction.is_empty()
+            || fraction.len() > 6
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let micros: u64 = fraction.parse().map_err(|_| invalid())?;
+        let timestamp = Duration::from_secs(secs.parse().map_err(|_| invalid())?)
+            + Duration::from_micros(micros * 10u64.pow(6 - fraction.len() as u32));
+        let direction = match direction {
+            ">" => Direction::ToDevice,
+            "<" => Direction::FromDevice,
+            _ => return Err(invalid()),
+        };
+        let report_type = match report_type {
+            "input" => ReportType::Input,
+            "output" => ReportType::Output,
+            "feature" => ReportType::Feature,
+            _ => return Err(invalid()),
+        };
+        let mut data = vec![u8::from_str_radix(report_id, 16).map_err(|_| invalid())?];
+        if payload != "-" {
+            if payload.len() % 2 != 0 {
+                return Err(invalid());
+            }
+            for i in (0..payload.len()).step_by(2) {
+                let byte = payload.get(i..i + 2).ok_or_else(invalid)?;
+                data.push(u8::from_str_radix(byte, 16).map_err(|_| invalid())?);
+            }
+        }
+        Ok(Some(Self {
+            timestamp,
+            interface: interface.parse().map_err(|_| invalid())?,
+            direction,
+            report_type,
+            data,
+        }))
+    }
+}
+
+/// Read entries of capture in `Format::Text`
+pub fn read_text(reader: impl BufRead) -> Ft260Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        entries.extend(Entry::from_line(&line?)?);
+    }
+    Ok(entries)
+}
+
+fn invalid_data(message: String) -> Ft260Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message).into()
+}
+
+/// Sink recording reports transferred by devices with monotonic timestamps, in `Format`.
+/// It can be shared by the interfaces of a chip, which are told by the interface number.
+/// Every report is written out as it's transferred, so the file keeps reports until a crash.
+///
+/// ```no_run
+/// use ft260hid::capture::{Capture, Format};
+/// use ft260hid::device::OpenOptions;
+///
+/// let dev = OpenOptions::new().interface(0).open().unwrap();
+/// let capture = Capture::create("ft260.log", Format::Text).unwrap();
+/// dev.start_capture(&capture, 0);
+/// ```
+pub struct Capture {
+    format: Format,
+    /// monotonic time when started
+    start: Instant,
+    /// wall-clock time when started, to which pcapng timestamps are relative
+    start_time: SystemTime,
+    /// ID given to each USB request block in pcapng
+    next_urb: AtomicU64,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl std::fmt::Debug for Capture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Capture")
+            .field("format", &self.format)
+            .field("start_time", &self.start_time)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Capture {
+    /// Start capture appended to file at `path`, which is created if it doesn't exist.
+    /// Appended pcapng begins a new section.
+    pub fn create(path: impl AsRef<Path>, format: Format) -> Ft260Result<Arc<Self>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Self::new(file, format)
+    }
+
+    /// Start capture written into `writer`
+    pub fn new<W: Write + Send + 'static>(writer: W, format: Format) -> Ft260Result<Arc<Self>> {
+        let capture = Self {
+            format,
+            start: Instant::now(),
+            start_time: SystemTime::now(),
+            next_urb: AtomicU64::new(1),
+            writer: Mutex::new(Box::new(writer)),
+        };
+        let header = match format {
+            Format::Text => {
+                let since_epoch = capture
+                    .start_time
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                format!(
+                    "# ft260hid capture 1, started at {}.{:06}\n",
+                    since_epoch.as_secs(),
+                    since_epoch.subsec_micros()
+                )
+                .into_bytes()
+            }
+            Format::Pcapng => pcapng::header(),
+        };
+        capture.write(&header)?;
+        Ok(Arc::new(capture))
+    }
+
+    /// Format of the capture
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Flush the writer
+    pub fn flush(&self) -> Ft260Result<()> {
+        Ok(self.writer.lock().unwrap().flush()?)
+    }
+
+    fn write(&self, bytes: &[u8]) -> Ft260Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(bytes)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Append a report. Failure in writing is logged, not to fail the transfer.
+    pub(crate) fn record(
+        &self,
+        interface: u8,
+        direction: Direction,
+        report_type: ReportType,
+        data: &[u8],
+    ) {
+        let entry = Entry {
+            timestamp: self.start.elapsed(),
+            interface,
+            direction,
+            report_type,
+            data: data.to_vec(),
+        };
+        let bytes = match self.format {
+            Format::Text => {
+                let mut line = entry.to_line();
+                line.push('\n');
+                line.into_bytes()
+            }
+            Format::Pcapng => {
+                let urb = self.next_urb.fetch_add(1, Ordering::Relaxed);
+                pcapng::packets(&entry, self.start_time + entry.timestamp, urb)
+            }
+        };
+        if let Err(e) = self.write(&bytes) {
+            tracing::warn!(error = %e, "failed to write capture");
+        }
+    }
+}
+
+/// Capture set to a device, shared with its reading thread
+#[derive(Debug, Default)]
+pub(crate) struct Tap {
+    target: RwLock<Option<(Arc<Capture>, u8)>>,
+}
+
+impl Tap {
+    pub fn set(&self, capture: Option<(Arc<Capture>, u8)>) {
+        *self.target.write().unwrap() = capture;
+    }
+
+    pub fn record(&self, direction: Direction, report_type: ReportType, data: &[u8]) {
+        if let Some((capture, interface)) = self.target.read().unwrap().as_ref() {
+            capture.record(*interface, direction, report_type, data);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Blocks of pcapng with `LINKTYPE_USB_LINUX_MMAPPED`, each packet of which begins with 64 bytes of usbmon header.
+//! All values are little endian.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Direction, Entry, ReportType};
+
+const LINKTYPE_USB_LINUX_MMAPPED: u16 = 220;
+
+const XFER_INTERRUPT: u8 = 1;
+const XFER_CONTROL: u8 = 2;
+
+/// HID class requests and report type of feature in `wValue`
+const SET_REPORT: u8 = 0x09;
+const GET_REPORT: u8 = 0x01;
+const REPORT_TYPE_FEATURE: u8 = 0x03;
+
+/// Nominal bus and device address
+const BUS: u16 = 1;
+const DEVICE: u8 = 1;
+
+/// Status of URB submitted, `-EINPROGRESS`
+const STATUS_IN_PROGRESS: i32 = -115;
+
+/// Section Header Block followed by Interface Description Block
+pub(super) fn header() -> Vec<u8> {
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&0x1A2B3C4Du32.to_le_bytes()); // byte-order magic
+    shb.extend_from_slice(&1u16.to_le_bytes()); // major version
+    shb.extend_from_slice(&0u16.to_le_bytes()); // minor version
+    shb.extend_from_slice(&(-1i64).to_le_bytes()); // section length not specified
+    let mut idb = Vec::new();
+    idb.extend_from_slice(&LINKTYPE_USB_LINUX_MMAPPED.to_le_bytes());
+    idb.extend_from_slice(&0u16.to_le_bytes()); // reserved
+    idb.extend_from_slice(&0u32.to_le_bytes()); // no snapshot length limit
+    let mut bytes = block(0x0A0D0D0A, &shb);
+    bytes.extend(block(0x00000001, &idb));
+    bytes
+}
+
+/// Enhanced Packet Blocks of USB events transferring the report in `entry`
+pub(super) fn packets(entry: &Entry, time: SystemTime, urb: u64) -> Vec<u8> {
+    let interface = entry.interface;
+    let report_id = entry.data.first().copied().unwrap_or_default();
+    let len = entry.data.len() as u16;
+    // the report is carried by every event but submission of GET_REPORT
+    let event = |kind: u8, xfer: u8, endpoint: u8, setup: Option<[u8; 8]>, with_data: bool| Event {
+        urb,
+        kind,
+        xfer,
+        endpoint,
+        setup,
+        data_len: len as u32,
+        data: if with_data { &entry.data } else { &[] },
+    };
+    let events = match (entry.report_type, entry.direction) {
+        (ReportType::Feature, Direction::ToDevice) => {
+            let setup = setup(0x21, SET_REPORT, report_id, interface, len);
+            vec![event(b'S', XFER_CONTROL, 0x00, Some(setup), true)]
+        }
+        (ReportType::Feature, Direction::FromDevice) => {
+            let setup = setup(0xA1, GET_REPORT, report_id, interface, len);
+            vec![
+                event(b'S', XFER_CONTROL, 0x80, Some(setup), false),
+                event(b'C', XFER_CONTROL, 0x80, None, true),
+            ]
+        }
+        (_, Direction::ToDevice) => {
+            vec![event(
+                b'S',
+                XFER_INTERRUPT,
+                endpoint(0x02, interface),
+                None,
+                true,
+            )]
+        }
+        (_, Direction::FromDevice) => {
+            vec![event(
+                b'C',
+                XFER_INTERRUPT,
+                endpoint(0x81, interface),
+                None,
+                true,
+            )]
+        }
+    };
+    events
+        .iter()
+        .flat_map(|e| enhanced_packet(time, &e.to_bytes(time)))
+        .collect()
+}
+
+/// Nominal endpoint of `interface`, `0x81 + 2n` for IN and `0x02 + 2n` for OUT of interface `n`
+fn endpoint(base: u8, interface: u8) -> u8 {
+    base.wrapping_add(interface.wrapping_mul(2))
+}
+
+/// Setup packet of HID class request for feature report
+fn setup(request_type: u8, request: u8, report_id: u8, interface: u8, len: u16) -> [u8; 8] {
+    let [len_lo, len_hi] = len.to_le_bytes();
+    [
+        request_type,
+        request,
+        report_id,
+        REPORT_TYPE_FEATURE,
+        interface,
+        0,
+        len_lo,
+        len_hi,
+    ]
+}
+
+/// usbmon event, submission `S` or completion `C` of URB
+struct Event<'a> {
+    urb: u64,
+    kind: u8,
+    xfer: u8,
+    endpoint: u8,
+    setup: Option<[u8; 8]>,
+    /// length of data in URB
+    data_len: u32,
+    /// data captured in the event
+    data: &'a [u8],
+}
+
+impl Event<'_> {
+    fn to_bytes(&self, time: SystemTime) -> Vec<u8> {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let status = if self.kind == b'S' {
+            STATUS_IN_PROGRESS
+        } else {
+            0
+        };
+        let mut bytes = Vec::with_capacity(64 + self.data.len());
+        bytes.extend_from_slice(&self.urb.to_le_bytes());
+        bytes.extend_from_slice(&[self.kind, self.xfer, self.endpoint, DEVICE]);
+        bytes.extend_from_slice(&BUS.to_le_bytes());
+        // `0` if setup or data is present, else a character telling why
+        bytes.push(if self.setup.is_some() { 0 } else { b'-' });
+        bytes.push(if self.data.is_empty() { b'<' } else { 0 });
+        bytes.extend_from_slice(&(since_epoch.as_secs() as i64).to_le_bytes());
+        bytes.extend_from_slice(&(since_epoch.subsec_micros() as i32).to_le_bytes());
+        bytes.extend_from_slice(&status.to_le_bytes());
+        bytes.extend_from_slice(&self.data_len.to_le_bytes());
+        bytes.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.setup.unwrap_or_default());
+        bytes.extend_from_slice(&0i32.to_le_bytes()); // interval
+        bytes.extend_from_slice(&0i32.to_le_bytes()); // start frame
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // transfer flags
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // number of ISO descriptors
+        bytes.extend_from_slice(self.data);
+        bytes
+    }
+}
+
+/// Enhanced Packet Block on interface `0` with timestamp in microseconds
+fn enhanced_packet(time: SystemTime, packet: &[u8]) -> Vec<u8> {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64;
+    let mut body = Vec::with_capacity(20 + packet.len() + 3);
+    body.extend_from_slice(&0u32.to_le_bytes()); // interface ID
+    body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(micros as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // captured length
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // original length
+    body.extend_from_slice(packet);
+    block(0x00000006, &body)
+}
+
+/// Block of `block_type` with `body` padded to 32 bits
+fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let padded = body.len().next_multiple_of(4);
+    let total = (12 + padded) as u32;
+    let mut bytes = Vec::with_capacity(total as usize);
+    bytes.extend_from_slice(&block_type.to_le_bytes());
+    bytes.extend_from_slice(&total.to_le_bytes());
+    bytes.extend_from_slice(body);
+    bytes.resize(8 + padded, 0);
+    bytes.extend_from_slice(&total.to_le_bytes());
+    bytes
+}
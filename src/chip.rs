@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use crate::capture::Capture;
+pub use crate::codec::system::{
+    ChipMode, Clock, Gpio2Function, GpioAFunction, GpioGFunction, InterruptDuration,
+    InterruptTrigger, SuspendOutPolarity, SystemStatus,
+};
+use crate::device::{Device, OpenOptions, Role};
+use crate::hid::reports;
+use crate::io::{gpio::Gpio, i2c::I2c, interrupt::Interrupt, uart::Uart};
+use crate::power::Power;
+use crate::{Ft260Error, Ft260Result};
+
+impl Clock {
+    /// If UART baud rate `baud` is supported with this clock.
+    /// The datasheet documents the range of 1200 to 12M baud without limits of each clock,
+    /// so it is the same for every clock.
+    pub fn supports_uart_baud(&self, baud: u32) -> bool {
+        (UART_BAUD_MIN..=UART_BAUD_MAX).contains(&baud)
+    }
+
+    /// If I2C clock speed `kbps` is supported with this clock.
+    /// The datasheet documents the range of 60 to 3400 kbps without limits of each clock,
+    /// so it is the same for every clock.
+    pub fn supports_i2c_kbps(&self, kbps: u16) -> bool {
+        (I2C_KBPS_MIN..=I2C_KBPS_MAX).contains(&kbps)
+    }
+}
+
+const UART_BAUD_MIN: u32 = 1200;
+const UART_BAUD_MAX: u32 = 12_000_000;
+const I2C_KBPS_MIN: u16 = 60;
+const I2C_KBPS_MAX: u16 = 3400;
+
+/// How to choose the system clock when I2C speed or UART baud rate is configured.
+/// The clock is shared by I2C and UART of the chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockPolicy {
+    /// Keep the current clock, passing the rate to the chip as it is.
+    /// It only warns if the rate is out of the range supported with the clock.
+    Keep,
+    /// Switch to the lowest clock supporting the rate to save power,
+    /// and fail if no clock does
+    Lowest,
+}
+
+/// Communication rate configured with a system clock.
+/// The unit is baud for UART and kbps for I2C.
+/// It is the rate passed to the chip, which doesn't report the rate it actually generates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    /// System clock selected for the rate
+    pub clock: Clock,
+    /// Rate requested
+    pub requested: u32,
+}
+
+impl Rate {
+    /// Pick `current` clock if `policy` is `Keep`,
+    /// or the lowest clock which `supports` the rate if `Lowest`
+    pub(crate) fn select(
+        current: Clock,
+        policy: ClockPolicy,
+        requested: u32,
+        supports: impl Fn(Clock) -> bool,
+    ) -> Option<Rate> {
+        let clock = match policy {
+            ClockPolicy::Keep => Some(current),
+            ClockPolicy::Lowest => Clock::ALL.into_iter().find(|c| supports(*c)),
+        }?;
+        Some(Rate { clock, requested })
+    }
+}
+
+/// Part number of FT260
+pub const PART_NUMBER_FT260: u16 = 0x0260;
+
+/// Firmware version of the chip, ordered by minor version then revision.
+/// No firmware quirk is known to the library, so behaviour depending on the version
+/// can be gated by comparing it, e.g. `info.firmware >= FirmwareVersion { .. }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    /// Minor version of the chip
+    pub minor_version: u8,
+    /// Firmware revision
+    pub revision: u8,
+}
+
+impl std::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.minor_version, self.revision)
+    }
+}
+
+/// Decoded Chip Code feature report (0xA0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipInfo {
+    /// Chip part number, `0x0260` for FT260
+    pub part_number: u16,
+    /// Firmware version
+    pub firmware: FirmwareVersion,
+}
+
+impl ChipInfo {
+    pub(crate) fn from_hid(code: &reports::ChipCode) -> Self {
+        Self {
+            part_number: code.part_number,
+            firmware: FirmwareVersion {
+                minor_version: code.minor_version,
+                revision: code.revision,
+            },
+        }
+    }
+
+    /// If the chip is FT260
+    pub fn is_ft260(&self) -> bool {
+        self.part_number == PART_NUMBER_FT260
+    }
+}
+
+impl std::fmt::Display for ChipInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FT{:04X} firmware {}", self.part_number, self.firmware)
+    }
+}
+
+/// Handle of one physical FT260 chip holding all of its HID interfaces.
+/// `I2c`, `Uart` and `Gpio` are created on the right interface according to the chip mode.
+#[derive(Debug)]
+pub struct Chip {
+    mode: ChipMode,
+    devices: Vec<Device>,
+}
+
+impl Chip {
+    /// Open every HID interface of the chip selected by `options`.
+    /// Interface number given to `options` is used only to find the chip.
+    pub fn open(options: &OpenOptions) -> Ft260Result<Self> {
+        let descs = options.find_chip()?;
+        let mut devices = Vec::new();
+        for desc in descs.iter() {
+            devices.push(options.open_descriptor(desc)?);
+        }
+        Self::from_devices(devices)
+    }
+
+    /// Create `Chip` from devices already opened.
+    /// `devices[n]` should be HID interface `n` of one chip.
+    /// The devices share ownership of GPIO groups from then on.
+    pub fn from_devices(devices: Vec<Device>) -> Ft260Result<Self> {
+        let first = devices.first().ok_or_else(|| Ft260Error::OpenError {
+            kind: crate::OpenErrorKind::NotFound,
+            message: "no interface is given".to_string(),
+        })?;
+        let mode = first.system_status()?.chip_mode;
+        if devices.len() != mode.interface_count() {
+            return Err(Ft260Error::OpenError {
+                kind: crate::OpenErrorKind::WrongInterface,
+                message: format!(
+                    "chip mode {:?} has {} interface(s) but {} found",
+                    mode,
+                    mode.interface_count(),
+                    devices.len()
+                ),
+            });
+        }
+        for device in &devices[1..] {
+            device.share_pins(first);
+        }
+        Ok(Self { mode, devices })
+    }
+
+    /// Chip mode read when the chip was opened
+    pub fn mode(&self) -> ChipMode {
+        self.mode
+    }
+
+    /// `Device` of HID interface `interface`
+    pub fn device(&self, interface: usize) -> Option<&Device> {
+        self.devices.get(interface)
+    }
+
+    fn device_for(&self, role: Role) -> Ft260Result<&Device> {
+        let interface = match role {
+            Role::I2c => self.mode.i2c_interface(),
+            Role::Uart => self.mode.uart_interface(),
+        };
+        interface
+            .and_then(|n| self.devices.get(n))
+            .ok_or(Ft260Error::Unavailable {
+                role,
+                mode: self.mode,
+            })
+    }
+
+    /// Start recording reports of every interface into `capture`, labelled with the interface numbers
+    pub fn start_capture(&self, capture: &Arc<Capture>) {
+        for (interface, device) in self.devices.iter().enumerate() {
+            device.start_capture(capture, interface as u8);
+        }
+    }
+
+    /// Stop recording reports of every interface
+    pub fn stop_capture(&self) {
+        for device in &self.devices {
+            device.stop_capture();
+        }
+    }
+
+    /// Create instance to control I2C features on I2C interface
+    pub fn i2c(&self) -> Ft260Result<I2c> {
+        Ok(self.device_for(Role::I2c)?.i2c())
+    }
+
+    /// Create instance to control UART features on UART interface
+    pub fn uart(&self) -> Ft260Result<Uart> {
+        Ok(self.device_for(Role::Uart)?.uart())
+    }
+
+    /// Create instance to control GPIO features.
+    /// GPIO is available on any interface in every chip mode.
+    pub fn gpio(&self) -> Gpio {
+        self.devices[0].gpio()
+    }
+
+    /// Create instance to receive interrupts on INTRIN, which are reported on UART interface
+    pub fn interrupt(&self) -> Ft260Result<Interrupt> {
+        Ok(self.device_for(Role::Uart)?.interrupt())
+    }
+
+    /// Create instance to query and configure USB suspend behaviour of the chip
+    pub fn power(&self) -> Power {
+        self.devices[0].power()
+    }
+}
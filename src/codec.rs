@@ -0,0 +1,237 @@
+/// submodule to encode and decode GPIO reports
+pub mod gpio;
+/// submodule to encode and decode I2C reports
+pub mod i2c;
+/// submodule to encode and decode System Setting, Chip Code and Interrupt Status reports
+pub mod system;
+/// submodule to encode and decode UART reports
+pub mod uart;
+
+use core::fmt;
+
+back_to_enum! {
+/// HID report IDs of FT260
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ReportId
+{
+    FeatChipCode = 0xA0, // Feature Chip code
+    FeatSystemSetting = 0xA1, // Feature System Setting
+    FeatGpio = 0xB0, // Feature GPIO
+    InInterruptStatus = 0xB1, // Input Interrupt Status(from UART interface)
+    /// I2c reports
+    FeatI2cStatus = 0xC0, // Feature I2C Status
+    OutI2cReadRequest = 0xC2, // Output I2C Read Request
+    // 0xD0 to 0xDE Input, Output I2C Report
+    InOutI2cReport04 = 0xD0,
+    InOutI2cReport08 = 0xD1,
+    InOutI2cReport0C = 0xD2,
+    InOutI2cReport10 = 0xD3,
+    InOutI2cReport14 = 0xD4,
+    InOutI2cReport18 = 0xD5,
+    InOutI2cReport1C = 0xD6,
+    InOutI2cReport20 = 0xD7,
+    InOutI2cReport24 = 0xD8,
+    InOutI2cReport28 = 0xD9,
+    InOutI2cReport2C = 0xDA,
+    InOutI2cReport30 = 0xDB,
+    InOutI2cReport34 = 0xDC,
+    InOutI2cReport38 = 0xDD,
+    InOutI2cReport3C = 0xDE,
+    InOutI2cReportOverflow = 0xDF,
+    /// Uart reports
+    FeatUartStatus = 0xE0, // Feature UART Status
+    FeatUartRiAndDcdStatus = 0xE2, // Feature UART RI and DCD Status
+    // 0xF0 to 0xFE Input, Output UART Report
+    InOutUartReport04 = 0xF0,
+    InOutUartReport08 = 0xF1,
+    InOutUartReport0C = 0xF2,
+    InOutUartReport10 = 0xF3,
+    InOutUartReport14 = 0xF4,
+    InOutUartReport18 = 0xF5,
+    InOutUartReport1C = 0xF6,
+    InOutUartReport20 = 0xF7,
+    InOutUartReport24 = 0xF8,
+    InOutUartReport28 = 0xF9,
+    InOutUartReport2C = 0xFA,
+    InOutUartReport30 = 0xFB,
+    InOutUartReport34 = 0xFC,
+    InOutUartReport38 = 0xFD,
+    InOutUartReport3C = 0xFE,
+    InOutUartReportOverflow = 0xFF,
+}
+}
+
+/// Requests of System Setting feature report (0xA1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Request {
+    SetClock = 0x01,
+    SetI2cMode = 0x02,
+    SetUartMode = 0x03,
+    /// Usage of GPIO3
+    EnableInterruptWakeUp = 0x05,
+    /// Usage of GPIO2
+    SelectGpio2Function = 0x06,
+    EnableUartDcdRi = 0x07,
+    /// Usage of GPIOA
+    SelectGpioAFunction = 0x08,
+    /// Usage of GPIOG
+    SelectGpioGFunction = 0x09,
+    SetInterruptTriggerCondition = 0x0A,
+    /// Suspend Output Polarity
+    SetSuspendOutPol = 0x0B,
+    /// Enable UART RI remote wakeup capability
+    EnableUartRiWakeup = 0x0C,
+    SetUartRiWakeupConfig = 0x0D,
+    ResetI2c = 0x20,
+    SetI2cClockSpeed = 0x22,
+    ResetUart = 0x40,
+    ConfigureUart = 0x41,
+    SetUartBaudRate = 0x42,
+    SetUartDataBits = 0x43,
+    SetUartParity = 0x44,
+    SetUartStopBit = 0x45,
+    SetUartBreaking = 0x46,
+    SetUartXonXoff = 0x49,
+    // FT260_SetParam_U8 Params
+    SetDriveStrength0 = 0x50,
+    SetDriveStrength3 = 0x51,
+    SetDriveStrength4 = 0x52,
+    SetSlewRate0 = 0x53,
+    SetGpioPullUp = 0x61,    // takes mask of GPIO 0-5
+    SetGpioOpenDrain = 0x62, // takes mask of GPIO 0-5
+    SetGpioPullDown = 0x63,  // takes mask of GPIO 0-5
+    SetGpioSlewRate = 0x65,  // takes mask of GPIO 0-5
+    // FT260_SetParam_U16 Params
+    SetSuspendModeGpio0 = 0x10,   // GPIO 0-5
+    SetSuspendModeGpioA = 0x11,   // GPIO A-H
+    SetDriveStrengthGpio0 = 0x64, // GPIO 0-5
+}
+
+/// Maximum length of HID report including ID
+pub const REPORT_LENGTH_MAX: usize = 64;
+
+/// Maximum bytes of I2C or UART data carried by a report
+pub const DATA_LENGTH_MAX: usize = 0x3C;
+
+/// Errors in encoding or decoding reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Buffer to encode into is shorter than the report
+    BufferTooSmall { needed: usize, actual: usize },
+    /// Data doesn't fit in a report
+    DataTooLong { len: usize, max: usize },
+    /// Report to decode has another ID
+    WrongReportId { report_id: u8 },
+    /// Report to decode is shorter than its layout
+    ShortReport {
+        report_id: u8,
+        len: usize,
+        needed: usize,
+    },
+    /// Byte is not a valid value of the field
+    InvalidValue { value: u8 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BufferTooSmall { needed, actual } => {
+                write!(
+                    f,
+                    "buffer of {} byte(s) can't hold {} byte(s)",
+                    actual, needed
+                )
+            }
+            Error::DataTooLong { len, max } => {
+                write!(
+                    f,
+                    "{} byte(s) of data exceed {} byte(s) of report",
+                    len, max
+                )
+            }
+            Error::WrongReportId { report_id } => {
+                write!(f, "unexpected report ID {:#04x}", report_id)
+            }
+            Error::ShortReport {
+                report_id,
+                len,
+                needed,
+            } => write!(
+                f,
+                "report {:#04x} is {} byte(s) long, not {}",
+                report_id, len, needed
+            ),
+            Error::InvalidValue { value } => write!(f, "invalid value {:#04x}", value),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Result type of encoding and decoding
+pub type CodecResult<T> = Result<T, Error>;
+
+/// Check `report` has ID `id` and at least `needed` bytes
+fn expect(report: &[u8], id: ReportId, needed: usize) -> CodecResult<()> {
+    let Some(&report_id) = report.first() else {
+        return Err(Error::ShortReport {
+            report_id: id as u8,
+            len: 0,
+            needed,
+        });
+    };
+    if report_id != id as u8 {
+        return Err(Error::WrongReportId { report_id });
+    }
+    if report.len() < needed {
+        return Err(Error::ShortReport {
+            report_id,
+            len: report.len(),
+            needed,
+        });
+    }
+    Ok(())
+}
+
+/// Get the front of `buf` to encode a report of `needed` bytes
+fn reserve(buf: &mut [u8], needed: usize) -> CodecResult<&mut [u8]> {
+    let actual = buf.len();
+    buf.get_mut(..needed)
+        .ok_or(Error::BufferTooSmall { needed, actual })
+}
+
+/// Data report ID for `len` bytes, counted from `base` by 4 bytes a step
+fn data_report_id(base: u8, len: usize) -> CodecResult<ReportId> {
+    if len > DATA_LENGTH_MAX {
+        return Err(Error::DataTooLong {
+            len,
+            max: DATA_LENGTH_MAX,
+        });
+    }
+    let step = len.saturating_sub(1) / 4;
+    ReportId::try_from(base + step as u8)
+}
+
+/// Data capacity of report `id` among data reports from `base`, or `None` for other reports
+fn data_capacity(base: u8, id: ReportId) -> Option<usize> {
+    let step = (id as u8).checked_sub(base)? as usize;
+    let capacity = 4 * (step + 1);
+    (capacity <= DATA_LENGTH_MAX).then_some(capacity)
+}
+
+/// Data following the length byte of input report among data reports from `base`
+fn decode_data(base: u8, report: &[u8]) -> CodecResult<&[u8]> {
+    let report_id = report.first().copied().unwrap_or(base);
+    let id = ReportId::try_from(report_id).map_err(|_| Error::WrongReportId { report_id })?;
+    let capacity = data_capacity(base, id).ok_or(Error::WrongReportId { report_id })?;
+    expect(report, id, 2)?;
+    let len = report[1] as usize;
+    if len > capacity {
+        return Err(Error::InvalidValue { value: report[1] });
+    }
+    expect(report, id, 2 + len)?;
+    Ok(&report[2..2 + len])
+}
@@ -0,0 +1,66 @@
+use super::{expect, reserve, CodecResult, ReportId};
+
+use bitflags::bitflags;
+
+bitflags! {
+/// Set of GPIO pins, with GPIO 0-5 in bit 0-5 and GPIO A-H in bit 6-13
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GpioPinNum: u16 {
+    const GPIO_0  = 1 << 0;
+    const GPIO_1  = 1 << 1;
+    const GPIO_2  = 1 << 2;
+    const GPIO_3  = 1 << 3;
+    const GPIO_4  = 1 << 4;
+    const GPIO_5  = 1 << 5;
+    const GPIO_A  = 1 << 6;
+    const GPIO_B  = 1 << 7;
+    const GPIO_C  = 1 << 8;
+    const GPIO_D  = 1 << 9;
+    const GPIO_E  = 1 << 10;
+    const GPIO_F  = 1 << 11;
+    const GPIO_G  = 1 << 12;
+    const GPIO_H  = 1 << 13;
+}
+}
+
+/// GPIO feature report (0xB0), both returned by Get Feature and sent by Set Feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpioReport {
+    /// Pins at high level
+    pub val: GpioPinNum,
+    /// Pins in output direction, and the others are inputs
+    pub dir: GpioPinNum,
+}
+
+impl GpioReport {
+    /// Length of the report including ID
+    pub const LENGTH: usize = 5;
+
+    /// Decode report returned by Get Feature
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::FeatGpio, Self::LENGTH)?;
+        Ok(Self {
+            val: join(report[1], report[3]),
+            dir: join(report[2], report[4]),
+        })
+    }
+
+    /// Encode report to send by Set Feature into `buf`, returning its length
+    pub fn encode(&self, buf: &mut [u8]) -> CodecResult<usize> {
+        let report = reserve(buf, Self::LENGTH)?;
+        let (val, ex_val) = split(self.val);
+        let (dir, ex_dir) = split(self.dir);
+        report.copy_from_slice(&[ReportId::FeatGpio as u8, val, dir, ex_val, ex_dir]);
+        Ok(Self::LENGTH)
+    }
+}
+
+/// Pins from bits of GPIO 0-5 and GPIO A-H, which are separate bytes in the report
+fn join(bits: u8, ex_bits: u8) -> GpioPinNum {
+    GpioPinNum::from_bits_truncate((bits & 0x3F) as u16 | (ex_bits as u16) << 6)
+}
+
+/// Bits of GPIO 0-5 and GPIO A-H in the report for `pins`
+fn split(pins: GpioPinNum) -> (u8, u8) {
+    ((pins.bits() & 0x3F) as u8, (pins.bits() >> 6) as u8)
+}
@@ -0,0 +1,109 @@
+use super::{data_capacity, data_report_id, decode_data, expect, reserve, CodecResult, ReportId};
+
+use bitflags::bitflags;
+
+bitflags! {
+  /// I2C controller status in I2C Status feature report (0xC0),
+  /// as in "LibFT260-v1.1.5\samples\I2C\i2c.cpp"
+  ///   bit 0 = controller busy: all other status bits invalid
+  ///   bit 1 = error condition
+  ///   bit 2 = slave address was not acknowledged during last operation
+  ///   bit 3 = data not acknowledged during last operation
+  ///   bit 4 = arbitration lost during last operation
+  ///   bit 5 = controller idle
+  ///   bit 6 = bus busy
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+  pub struct I2cBusStatus : u8
+  {
+    const ControllerBusy    = 0x01;
+    const Error             = 0x02;
+    const AddressNack       = 0x04;
+    const DataNack          = 0x08;
+    const ArbitrationLost   = 0x10;
+    const ControllerIdle    = 0x20;
+    const BusBusy           = 0x40;
+  }
+}
+
+bitflags! {
+  /// START and STOP conditions generated around the data of I2C report
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+  pub struct I2cCondition : u8
+  {
+    const None            = 0;
+    const Start           = 2;
+    const ReStart         = 3;
+    const Stop            = 4;
+    const StartAndStop    = 6;
+    const ReStartAndStop  = 7;
+  }
+}
+
+const DATA_REPORT_BASE: u8 = ReportId::InOutI2cReport04 as u8;
+
+/// I2C data report (0xD0-0xDE) to carry `len` bytes
+pub fn report_id(len: usize) -> CodecResult<ReportId> {
+    data_report_id(DATA_REPORT_BASE, len)
+}
+
+/// Bytes of data I2C data report `id` can carry, or `None` if it's not I2C data report
+pub fn capacity(id: ReportId) -> Option<usize> {
+    data_capacity(DATA_REPORT_BASE, id)
+}
+
+/// I2C Status feature report (0xC0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cStatus {
+    pub bus: I2cBusStatus,
+    /// Clock speed in kHz
+    pub kbps: u16,
+}
+
+impl I2cStatus {
+    /// Length of the report including ID
+    pub const LENGTH: usize = 5;
+
+    /// Decode report returned by Get Feature
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::FeatI2cStatus, 4)?;
+        Ok(Self {
+            bus: I2cBusStatus::from_bits_retain(report[1]),
+            kbps: u16::from_le_bytes([report[2], report[3]]),
+        })
+    }
+}
+
+/// Encode I2C Read Request output report (0xC2) into `buf`, returning its length
+pub fn encode_read_request(
+    buf: &mut [u8],
+    addr: u8,
+    cond: I2cCondition,
+    len: u16,
+) -> CodecResult<usize> {
+    let report = reserve(buf, 5)?;
+    let [lo, hi] = len.to_le_bytes();
+    report.copy_from_slice(&[ReportId::OutI2cReadRequest as u8, addr, cond.bits(), lo, hi]);
+    Ok(5)
+}
+
+/// Encode I2C data output report to write `data` into `buf`, padded with zeros up to the size of the report ID.
+/// Returns the length of the report.
+pub fn encode_write(
+    buf: &mut [u8],
+    addr: u8,
+    cond: I2cCondition,
+    data: &[u8],
+) -> CodecResult<usize> {
+    let id = report_id(data.len())?;
+    let len = 4 + capacity(id).unwrap_or_default();
+    let report = reserve(buf, len)?;
+    report.fill(0);
+    report[..4].copy_from_slice(&[id as u8, addr, cond.bits(), data.len() as u8]);
+    report[4..4 + data.len()].copy_from_slice(data);
+    Ok(len)
+}
+
+/// Decode I2C data input report (0xD0-0xDE), returning the data it carries
+pub fn decode_input(report: &[u8]) -> CodecResult<&[u8]> {
+    decode_data(DATA_REPORT_BASE, report)
+}
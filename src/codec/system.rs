@@ -0,0 +1,338 @@
+use super::{expect, reserve, CodecResult, Error, ReportId, Request};
+
+use bitflags::bitflags;
+
+use super::uart::Mode;
+use crate::hid::consts::{
+    self, ClkCtl, Gpio2Function as HidGpio2Function, GpioAFunction as HidGpioAFunction,
+    GpioGFunction as HidGpioGFunction, HidOverI2cEnableMode, I2cEnableMode,
+    InterruptDuration as HidInterruptDuration, InterruptTrigger as HidInterruptTrigger,
+    PowerSavingEnableMode, PwrEnStatus, SuspendOutPol, SuspendStatus, UartEnableMode,
+    WakeupIntEnableMode,
+};
+
+/// Chip mode configured by `DCNF0` and `DCNF1` pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipMode {
+    /// `DCNF1=0`, `DCNF0=0`: I2C on interface 0 and UART on interface 1
+    All,
+    /// `DCNF1=0`, `DCNF0=1`: I2C only on interface 0
+    I2c,
+    /// `DCNF1=1`, `DCNF0=0`: UART only on interface 0
+    Uart,
+    /// `DCNF1=1`, `DCNF0=1`: I2C on interface 0 and UART on interface 1
+    Both,
+}
+
+impl ChipMode {
+    pub(crate) fn from_hid_const(mode: consts::ChipMode) -> Self {
+        let dcnf0 = mode.contains(consts::ChipMode::Dcnf0);
+        let dcnf1 = mode.contains(consts::ChipMode::Dcnf1);
+        match (dcnf1, dcnf0) {
+            (false, false) => Self::All,
+            (false, true) => Self::I2c,
+            (true, false) => Self::Uart,
+            (true, true) => Self::Both,
+        }
+    }
+
+    /// HID interface number which works as I2C controller
+    pub fn i2c_interface(&self) -> Option<usize> {
+        match self {
+            Self::All | Self::Both | Self::I2c => Some(0),
+            Self::Uart => None,
+        }
+    }
+
+    /// HID interface number which works as UART
+    pub fn uart_interface(&self) -> Option<usize> {
+        match self {
+            Self::All | Self::Both => Some(1),
+            Self::Uart => Some(0),
+            Self::I2c => None,
+        }
+    }
+
+    /// The number of HID interfaces exposed in this mode
+    pub fn interface_count(&self) -> usize {
+        match self {
+            Self::All | Self::Both => 2,
+            Self::I2c | Self::Uart => 1,
+        }
+    }
+}
+
+hid_const_compatible! {
+  ClkCtl,
+  /// System clock of the chip
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Clock {
+    /// 12 MHz
+    Mhz12,
+    /// 24 MHz
+    Mhz24,
+    /// 48 MHz (default)
+    Mhz48,
+  }
+}
+
+impl Clock {
+    /// All clocks in ascending order of frequency
+    pub const ALL: [Clock; 3] = [Clock::Mhz12, Clock::Mhz24, Clock::Mhz48];
+
+    /// Frequency in Hz
+    pub fn hz(&self) -> u32 {
+        match self {
+            Clock::Mhz12 => 12_000_000,
+            Clock::Mhz24 => 24_000_000,
+            Clock::Mhz48 => 48_000_000,
+        }
+    }
+}
+
+hid_const_compatible! {
+  HidGpio2Function,
+  /// Function of GPIO2 (DIO7) pin
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Gpio2Function {
+    /// GPIO2
+    Gpio,
+    /// Indicator of USB suspend state (default)
+    SuspOut,
+    /// Power enable indicator when the chip is USB enumerated
+    PwrEn,
+    /// LED driving source when data is transmitted on UART TX
+    TxLed,
+  }
+}
+
+hid_const_compatible! {
+  HidGpioAFunction,
+  /// Function of GPIOA (DIO0) pin
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum GpioAFunction {
+    /// GPIOA
+    Gpio,
+    /// Indicator of UART transmitting active (default)
+    TxActive,
+    /// LED driving source when data is transmitted on UART TX
+    TxLed,
+  }
+}
+
+hid_const_compatible! {
+  HidGpioGFunction,
+  /// Function of GPIOG (DIO13) pin
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum GpioGFunction {
+    /// GPIOG
+    Gpio,
+    /// Power enable indicator when the chip is USB enumerated, active low
+    PwrEn,
+    /// LED driving source when data is received on UART RX
+    RxLed,
+    /// Battery charger detection indicator (default)
+    BcdDet,
+  }
+}
+
+hid_const_compatible! {
+  SuspendOutPol,
+  /// Polarity of SUSPOUT output
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum SuspendOutPolarity {
+    /// Active high (default)
+    High,
+    /// Active low
+    Low,
+  }
+}
+
+hid_const_compatible! {
+  HidInterruptTrigger,
+  /// Trigger condition of interrupt input on GPIO3 (DIO8)
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum InterruptTrigger {
+    /// Rising edge
+    Rising,
+    /// High level
+    High,
+    /// Falling edge
+    Falling,
+    /// Low level
+    Low,
+  }
+}
+
+hid_const_compatible! {
+  HidInterruptDuration,
+  /// Duration of the level to trigger interrupt
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum InterruptDuration {
+    /// 1 ms
+    Ms1,
+    /// 5 ms
+    Ms5,
+    /// 30 ms
+    Ms30,
+  }
+}
+
+/// Chip Code feature report (0xA0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipCode {
+    pub part_number: u16,
+    pub minor_version: u8,
+    pub revision: u8,
+}
+
+impl ChipCode {
+    /// Length of the report including ID
+    pub const LENGTH: usize = 13;
+
+    /// Decode report returned by Get Feature
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::FeatChipCode, 5)?;
+        Ok(Self {
+            part_number: u16::from_be_bytes([report[1], report[2]]),
+            minor_version: report[3],
+            revision: report[4],
+        })
+    }
+}
+
+/// System Status feature report (0xA1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemStatus {
+    /// Chip mode configured by `DCNF0` and `DCNF1` pins
+    pub chip_mode: ChipMode,
+    /// System clock
+    pub clock: Clock,
+    /// The chip is in USB suspend state
+    pub suspended: bool,
+    /// The chip is USB enumerated and PWREN is asserted
+    pub pwren_ready: bool,
+    /// I2C controller is enabled
+    pub i2c_enabled: bool,
+    /// UART flow control mode. `Mode::Off` means UART pins work as GPIO
+    pub uart_mode: Mode,
+    /// HID-over-I2C is configured
+    pub hid_over_i2c: bool,
+    /// Function of GPIO2 pin
+    pub gpio2_function: Gpio2Function,
+    /// Function of GPIOA pin
+    pub gpio_a_function: GpioAFunction,
+    /// Function of GPIOG pin
+    pub gpio_g_function: GpioGFunction,
+    /// Polarity of SUSPOUT output
+    pub suspend_out_polarity: SuspendOutPolarity,
+    /// GPIO3 works as interrupt/wakeup input instead of GPIO
+    pub wakeup_interrupt_enabled: bool,
+    /// Trigger condition of interrupt input
+    pub interrupt_trigger: InterruptTrigger,
+    /// Level duration of interrupt input, `None` for edge triggers
+    pub interrupt_duration: Option<InterruptDuration>,
+    /// Power saving mode is enabled
+    pub power_saving_enabled: bool,
+}
+
+impl SystemStatus {
+    /// Length of the report including ID
+    pub const LENGTH: usize = 26;
+
+    /// Decode report returned by Get Feature
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::FeatSystemSetting, Self::LENGTH)?;
+        // interrupt condition has trigger type in bit 0-1 and level duration in bit 2-3
+        let interrupt_trigger =
+            InterruptTrigger::from_hid_const(&HidInterruptTrigger::try_from(report[13] & 0x03)?);
+        // duration is meaningless for edges, whatever the chip reports in bit 2-3
+        let interrupt_duration = match (interrupt_trigger, report[13] & 0x0C) {
+            (InterruptTrigger::Rising | InterruptTrigger::Falling, _) | (_, 0) => None,
+            (_, d) => Some(InterruptDuration::from_hid_const(
+                &HidInterruptDuration::try_from(d)?,
+            )),
+        };
+        Ok(Self {
+            chip_mode: ChipMode::from_hid_const(consts::ChipMode::from_bits_truncate(report[1])),
+            clock: Clock::from_hid_const(&ClkCtl::try_from(report[2])?),
+            suspended: SuspendStatus::try_from(report[3])? == SuspendStatus::Suspended,
+            pwren_ready: PwrEnStatus::try_from(report[4])? == PwrEnStatus::Ready,
+            i2c_enabled: I2cEnableMode::try_from(report[5])? == I2cEnableMode::Enabled,
+            uart_mode: Mode::from_hid_const(&UartEnableMode::try_from(report[6])?),
+            hid_over_i2c: HidOverI2cEnableMode::try_from(report[7])?
+                == HidOverI2cEnableMode::Configured,
+            gpio2_function: Gpio2Function::from_hid_const(&HidGpio2Function::try_from(report[8])?),
+            gpio_a_function: GpioAFunction::from_hid_const(&HidGpioAFunction::try_from(report[9])?),
+            gpio_g_function: GpioGFunction::from_hid_const(&HidGpioGFunction::try_from(
+                report[10],
+            )?),
+            suspend_out_polarity: SuspendOutPolarity::from_hid_const(&SuspendOutPol::try_from(
+                report[11],
+            )?),
+            wakeup_interrupt_enabled: WakeupIntEnableMode::try_from(report[12])?
+                == WakeupIntEnableMode::Enabled,
+            interrupt_trigger,
+            interrupt_duration,
+            power_saving_enabled: PowerSavingEnableMode::try_from(report[14])?
+                == PowerSavingEnableMode::Enable,
+        })
+    }
+
+    /// Decode only the system clock from report returned by Get Feature,
+    /// not to fail by unknown values of other fields
+    pub fn decode_clock(report: &[u8]) -> CodecResult<Clock> {
+        expect(report, ReportId::FeatSystemSetting, 3)?;
+        Ok(Clock::from_hid_const(&ClkCtl::try_from(report[2])?))
+    }
+}
+
+/// Encode System Setting request (0xA1) with its parameters into `buf`,
+/// returning the length of the report
+pub fn encode_request(buf: &mut [u8], request: Request, params: &[u8]) -> CodecResult<usize> {
+    let len = 2 + params.len();
+    if len > super::REPORT_LENGTH_MAX {
+        return Err(Error::DataTooLong {
+            len: params.len(),
+            max: super::REPORT_LENGTH_MAX - 2,
+        });
+    }
+    let report = reserve(buf, len)?;
+    report[0] = ReportId::FeatSystemSetting as u8;
+    report[1] = request as u8;
+    report[2..].copy_from_slice(params);
+    Ok(len)
+}
+
+/// Interrupt Status input report (0xB1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptStatus {
+    /// Interrupt on INTRIN pin has been triggered
+    pub intrin: bool,
+    /// Status of UART DCD and RI
+    pub uart: UartInterruptStatus,
+}
+
+impl InterruptStatus {
+    /// Length of the report including ID
+    pub const LENGTH: usize = 3;
+
+    /// Decode input report
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::InInterruptStatus, Self::LENGTH)?;
+        Ok(Self {
+            intrin: report[1] & 0x01 != 0,
+            uart: UartInterruptStatus::from_bits_truncate(report[2]),
+        })
+    }
+}
+
+bitflags! {
+  /// Levels of UART DCD and RI in Interrupt Status report (0xB1)
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+  pub struct UartInterruptStatus : u8
+  {
+    const Dcd  = 1;
+    const Ri  = 2;
+  }
+}
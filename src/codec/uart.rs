@@ -0,0 +1,173 @@
+use super::{
+    data_capacity, data_report_id, decode_data, expect, reserve, system, CodecResult, Error,
+    ReportId, Request,
+};
+
+use bitflags::bitflags;
+
+use crate::hid::consts::{UartBreaking, UartDataBits, UartEnableMode, UartParity, UartStopBit};
+
+hid_const_compatible! {
+  UartEnableMode,
+  /// UART Flow Control Mode
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Mode {
+    /// > "OFF, and switch UART pins to GPIO"
+    Off,
+    /// > "RTS_CTS mode (GPIOB =>RTSN, GPIOE =>CTSN)"
+    RtsCts,
+    /// > "DTR_DSR mode (GPIOF =>DTRN, GPIOH => DSRN)"
+    DtrDsr,
+    /// > "XON_XOFF (software flow control)"
+    XonXoff,
+    /// > "No flow control mode"
+    NoFlowControl,
+  }
+}
+
+hid_const_compatible! {
+  UartParity,
+  /// UART Parity mode
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Parity {
+    /// No parity
+    None,
+    /// Odd parity
+    Odd,
+    /// Even parity
+    Even,
+    /// Parity bit is always high
+    High,
+    /// Parity bit is always low
+    Low,
+  }
+}
+
+hid_const_compatible! {
+  UartStopBit,
+  /// Stop bit
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum StopBit {
+    /// one stop bit
+    One,
+    /// two stop bits
+    Two,
+  }
+}
+
+hid_const_compatible! {
+  UartBreaking,
+  /// > "When active the TXD line goes into ‘spacing’ state which causes a break in the receiving UART."
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum Breaking {
+    /// No break
+    NoBreak,
+    /// Break
+    Break,
+  }
+}
+
+hid_const_compatible! {
+  UartDataBits,
+  /// The number of UART data bits
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum DataBits {
+    /// 7 data bits
+    Seven,
+    /// 8 data bits
+    Eight,
+  }
+}
+
+bitflags! {
+  /// Levels of UART DCD and RI in UART DCD and RI status feature report (0xE2)
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+  pub struct UartDcdRiStatus : u8 {
+    const Dcd  = 1;
+    const Ri  = 2;
+  }
+}
+
+const DATA_REPORT_BASE: u8 = ReportId::InOutUartReport04 as u8;
+
+/// UART data report (0xF0-0xFE) to carry `len` bytes
+pub fn report_id(len: usize) -> CodecResult<ReportId> {
+    data_report_id(DATA_REPORT_BASE, len)
+}
+
+/// Bytes of data UART data report `id` can carry, or `None` if it's not UART data report
+pub fn capacity(id: ReportId) -> Option<usize> {
+    data_capacity(DATA_REPORT_BASE, id)
+}
+
+/// UART configuration, returned in UART Status feature report (0xE0) and sent by Configure UART request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UartConfig {
+    pub mode: Mode,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bit: StopBit,
+    pub breaking: Breaking,
+}
+
+impl UartConfig {
+    /// Length of UART Status report including ID
+    pub const LENGTH: usize = 10;
+
+    /// Decode UART Status report returned by Get Feature
+    pub fn decode(report: &[u8]) -> CodecResult<Self> {
+        expect(report, ReportId::FeatUartStatus, Self::LENGTH)?;
+        Ok(Self {
+            mode: Mode::from_hid_const(&UartEnableMode::try_from(report[1])?),
+            baud_rate: u32::from_le_bytes([report[2], report[3], report[4], report[5]]),
+            data_bits: DataBits::from_hid_const(&UartDataBits::try_from(report[6])?),
+            parity: Parity::from_hid_const(&UartParity::try_from(report[7])?),
+            stop_bit: StopBit::from_hid_const(&UartStopBit::try_from(report[8])?),
+            breaking: Breaking::from_hid_const(&UartBreaking::try_from(report[9])?),
+        })
+    }
+
+    /// Encode Configure UART request (0xA1) into `buf`, returning its length
+    pub fn encode_request(&self, buf: &mut [u8]) -> CodecResult<usize> {
+        let [b0, b1, b2, b3] = self.baud_rate.to_le_bytes();
+        system::encode_request(
+            buf,
+            Request::ConfigureUart,
+            &[
+                self.mode.to_hid_const() as u8,
+                b0,
+                b1,
+                b2,
+                b3,
+                self.data_bits.to_hid_const() as u8,
+                self.parity.to_hid_const() as u8,
+                self.stop_bit.to_hid_const() as u8,
+                self.breaking.to_hid_const() as u8,
+            ],
+        )
+    }
+}
+
+/// Decode UART DCD and RI status feature report (0xE2)
+pub fn decode_dcd_ri_status(report: &[u8]) -> CodecResult<UartDcdRiStatus> {
+    expect(report, ReportId::FeatUartRiAndDcdStatus, 2)?;
+    UartDcdRiStatus::from_bits(report[1]).ok_or(Error::InvalidValue { value: report[1] })
+}
+
+/// Encode UART data output report to write `data` into `buf`, padded with zeros up to the size of the report ID.
+/// Returns the length of the report.
+pub fn encode_write(buf: &mut [u8], data: &[u8]) -> CodecResult<usize> {
+    let id = report_id(data.len())?;
+    let len = 2 + capacity(id).unwrap_or_default();
+    let report = reserve(buf, len)?;
+    report.fill(0);
+    report[..2].copy_from_slice(&[id as u8, data.len() as u8]);
+    report[2..2 + data.len()].copy_from_slice(data);
+    Ok(len)
+}
+
+/// Decode UART data input report (0xF0-0xFE), returning the data it carries
+pub fn decode_input(report: &[u8]) -> CodecResult<&[u8]> {
+    decode_data(DATA_REPORT_BASE, report)
+}
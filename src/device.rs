@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+use std::ffi::CString;
 use std::sync::MutexGuard;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -6,18 +8,42 @@ use std::sync::{
 /// FT260 Device
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 // use hidapi crate only in this module
 use hidapi::{DeviceInfo, HidError};
 
-use crate::hid::rep_fifo::ReportFifo;
-use crate::io::{gpio::Gpio, i2c::I2c, uart::Uart};
-use crate::{Ft260Error, Ft260Result};
+use crate::capture::{Capture, Direction, ReportType, Tap};
+use crate::chip::{ChipInfo, Clock, ClockPolicy, Rate, SystemStatus};
+use crate::hid::rep_fifo::SharedFifo;
+use crate::hid::reports;
+use crate::io::gpio::{Gpio, Group, Owner};
+use crate::io::{i2c::I2c, interrupt::Interrupt, uart::Uart};
+use crate::power::{Power, RiWakeup};
+use crate::raw::Raw;
+use crate::transport::{Reconnect, Transport};
+use crate::{Ft260Error, Ft260Result, OpenErrorKind};
+
+mod lock;
+mod pins;
+use lock::{BusGuard, BusLock};
+pub(crate) use pins::PinClaim;
+use pins::PinRegistry;
 
 /// conversion of `HidError` into `Ft260Error`
 impl From<HidError> for Ft260Error {
     fn from(err: HidError) -> Self {
         match err {
+            HidError::IoError { ref error }
+                if matches!(
+                    error.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::BrokenPipe
+                ) =>
+            {
+                Ft260Error::Disconnected {
+                    message: format!("{}", err),
+                }
+            }
             HidError::HidApiError { message: _ }
             | HidError::HidApiErrorEmpty
             | HidError::FromWideCharError { wide_char: _ }
@@ -33,16 +59,225 @@ impl From<HidError> for Ft260Error {
     }
 }
 
-/// Data struct for FT260 device
+/// Paths of HID interfaces opened in this process
+static OPENED_PATHS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Claim of a HID interface path, released when dropped
+struct PathClaim {
+    path: String,
+}
+
+impl PathClaim {
+    fn acquire(path: &str) -> Option<Self> {
+        let mut opened = OPENED_PATHS.lock().unwrap();
+        if opened
+            .get_or_insert_with(HashSet::new)
+            .insert(path.to_string())
+        {
+            Some(Self {
+                path: path.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for PathClaim {
+    fn drop(&mut self) {
+        if let Some(opened) = OPENED_PATHS.lock().unwrap().as_mut() {
+            opened.remove(&self.path);
+        }
+    }
+}
+
+/// `Transport` implementation with `hidapi::HidDevice`.
+/// Input reports are read through a handle of their own if the interface can be opened twice,
+/// so that output and feature reports are not held up by a blocking read.
+struct HidTransport {
+    input: Mutex<hidapi::HidDevice>,
+    /// handle for output and feature reports, `None` to share `input`
+    output: Option<Mutex<hidapi::HidDevice>>,
+    claim: PathClaim,
+}
+
+/// Longest time to hold the handle shared for input and output in a blocking read
+const SHARED_READ_SLICE: Duration = Duration::from_millis(5);
+
+impl HidTransport {
+    /// Open the HID interface described with `desc`
+    fn open(desc: &DeviceDescriptor) -> Ft260Result<Self> {
+        let claim = PathClaim::acquire(&desc.path).ok_or_else(|| {
+            open_error(
+                OpenErrorKind::AlreadyOpen,
+                format!("{} has been opened in this process", desc.path),
+            )
+        })?;
+        let api = hidapi::HidApi::new()?;
+        let path = CString::new(desc.path.clone())
+            .map_err(|e| open_error(OpenErrorKind::Other, format!("{}", e)))?;
+        match api.open_path(&path) {
+            Ok(hid) => Ok(Self {
+                input: Mutex::new(hid),
+                // some platforms open HID interfaces exclusively
+                output: api.open_path(&path).ok().map(Mutex::new),
+                claim,
+            }),
+            Err(e) => {
+                let message = format!("{}", e);
+                let lower = message.to_lowercase();
+                let kind = if lower.contains("permission denied") || lower.contains("access") {
+                    OpenErrorKind::PermissionDenied
+                } else if lower.contains("busy") {
+                    OpenErrorKind::AlreadyOpen
+                } else {
+                    OpenErrorKind::Other
+                };
+                Err(open_error(kind, message))
+            }
+        }
+    }
+
+    fn output(&self) -> MutexGuard<'_, hidapi::HidDevice> {
+        self.output.as_ref().unwrap_or(&self.input).lock().unwrap()
+    }
+}
+
+impl Transport for HidTransport {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        if self.output.is_some() {
+            return Ok(self.input.lock().unwrap().read_timeout(buf, timeout)?);
+        }
+        // release the shared handle frequently for output and feature reports
+        let deadline =
+            (timeout >= 0).then(|| Instant::now() + Duration::from_millis(timeout as u64));
+        loop {
+            let slice = match deadline {
+                Some(d) => d
+                    .saturating_duration_since(Instant::now())
+                    .min(SHARED_READ_SLICE),
+                None => SHARED_READ_SLICE,
+            };
+            let sz = self
+                .input
+                .lock()
+                .unwrap()
+                .read_timeout(buf, slice.as_millis() as i32)?;
+            if sz > 0 || deadline.is_some_and(|d| Instant::now() >= d) {
+                return Ok(sz);
+            }
+            thread::yield_now();
+        }
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        Ok(self.output().write(data)?)
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        Ok(self.output().get_feature_report(buf)?)
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        Ok(self.output().send_feature_report(data)?)
+    }
+
+    fn is_present(&self) -> bool {
+        // hidraw backend fails with `HidApiError` on unplug, so look for the path on USB
+        match hidapi::HidApi::new() {
+            Ok(api) => api
+                .device_list()
+                .any(|inf| inf.path().to_string_lossy() == self.claim.path),
+            Err(_) => true,
+        }
+    }
+}
+
+/// Class of HID input reports, which has its own FIFO in `Device`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportClass {
+    /// I2C data (0xD0-0xDE)
+    I2c,
+    /// UART data (0xF0-0xFE)
+    Uart,
+    /// Interrupt status (0xB1)
+    Interrupt,
+}
+
+impl ReportClass {
+    /// Report ID representing the class in FIFO
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            ReportClass::I2c => 0xD0,
+            ReportClass::Uart => 0xF0,
+            ReportClass::Interrupt => 0xB0,
+        }
+    }
+}
+
+/// What to do with an input report arriving when its FIFO is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest report in FIFO to make space (default)
+    DropOldest,
+    /// Drop the report arriving
+    DropNewest,
+    /// Stop reading input reports of all classes until space is made,
+    /// so that the chip holds data or applies flow control
+    Backpressure,
+}
+
+/// Statistics of FIFO for a class of input reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FifoStats {
+    /// Number of reports in FIFO now
+    pub len: usize,
+    /// Maximum number of reports in FIFO
+    pub capacity: usize,
+    /// Policy on overflow
+    pub policy: OverflowPolicy,
+    /// Number of reports arrived, including dropped ones
+    pub received: u64,
+    /// Number of reports dropped on overflow
+    pub dropped: u64,
+    /// Maximum number of reports which have been in FIFO
+    pub high_water: usize,
+}
 
-#[derive(Debug)]
+/// Data struct for FT260 device.  
+/// Clones share the same HID interface and its reading thread, which stops when the last clone is dropped,
+/// so that `Gpio`, `I2c` and `Uart` instances can own it and be sent to or shared among threads.
+#[derive(Clone)]
 pub struct Device {
-    hid: Arc<Mutex<hidapi::HidDevice>>,
-    fifo: Arc<Mutex<ReportFifo>>,
+    inner: Arc<Inner>,
+}
+
+/// State of `Device` shared by its clones
+struct Inner {
+    transport: Arc<dyn Transport>,
+    fifo: Arc<SharedFifo>,
     reading: Arc<AtomicBool>,
-    handle: JoinHandle<()>,
+    connected: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    /// serializes I2C transactions made of several reports
+    i2c_lock: BusLock,
+    /// serializes UART reads and writes respectively
+    uart_read_lock: BusLock,
+    uart_write_lock: BusLock,
+    /// serializes read-modify-write of GPIO report
+    gpio_lock: BusLock,
+    /// functions owning GPIO groups, shared by devices of one `Chip`
+    pins: Mutex<Arc<PinRegistry>>,
+    /// capture recording reports, shared with the reading thread
+    tap: Arc<Tap>,
 }
 
+/// Timeout in milliseconds of each blocking read of input report,
+/// which bounds the time to stop the reading thread
+const READ_TIMEOUT_MS: i32 = 50;
+/// Interval to retry reading input report after the transport failed
+const READ_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Default USB Vendor ID for FT260 device
 pub const VID_DEFAULT: u16 = 0x0403;
 /// Default USB Product ID for FT260 device
@@ -58,148 +293,646 @@ pub fn open(interface: i32) -> Option<Device> {
 /// Open FT260 HID by explicit Vendor ID and Product ID.  
 /// `interface` should take `0` or `1` only.
 pub fn open_by_vid_pid(vendor_id: u16, product_id: u16, interface: i32) -> Option<Device> {
-    Device::try_new(vendor_id, product_id, interface, 0) // try find only the first one
+    OpenOptions::new()
+        .vid_pid(vendor_id, product_id)
+        .interface(interface)
+        .open() // try find only the first one
+        .ok()
+}
+
+/// Options to select which FT260 HID interface to open.  
+/// Conditions not given are not checked, and the first interface matched with all the others is opened.
+///
+/// ```no_run
+/// use ft260hid::device::OpenOptions;
+///
+/// let dev = OpenOptions::new()
+///     .serial_number("FT6QJ0KA")
+///     .interface(1)
+///     .open()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenOptions {
+    vendor_id: u16,
+    product_id: u16,
+    interface: Option<i32>,
+    serial_number: Option<String>,
+    path: Option<String>,
+    index: usize,
+    reconnect: Option<Duration>,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn open_error(kind: OpenErrorKind, message: String) -> Ft260Error {
+    Ft260Error::OpenError { kind, message }
+}
+
+impl OpenOptions {
+    /// Options to open the first FT260 interface with default Vendor ID and Product ID
+    pub fn new() -> Self {
+        Self {
+            vendor_id: VID_DEFAULT,
+            product_id: PID_DEFAULT,
+            interface: None,
+            serial_number: None,
+            path: None,
+            index: 0,
+            reconnect: None,
+        }
+    }
+
+    /// Select by explicit Vendor ID and Product ID
+    pub fn vid_pid(mut self, vendor_id: u16, product_id: u16) -> Self {
+        self.vendor_id = vendor_id;
+        self.product_id = product_id;
+        self
+    }
+
+    /// Select by HID interface number, `0` or `1`
+    pub fn interface(mut self, interface: i32) -> Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    /// Select by USB serial number string
+    pub fn serial_number(mut self, serial_number: &str) -> Self {
+        self.serial_number = Some(serial_number.to_string());
+        self
+    }
+
+    /// Select by HID path as `DeviceDescriptor::path`
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Select the `index`-th interface among the ones matched with the other conditions
+    pub fn index(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Reconnect automatically when the interface is lost, e.g. by a glitch of USB hub.
+    /// The same serial number and interface number are opened again within `timeout`,
+    /// and settings sent to the chip are restored as `transport::Reconnect` does.
+    pub fn reconnect(mut self, timeout: Duration) -> Self {
+        self.reconnect = Some(timeout);
+        self
+    }
+
+    /// Find the HID interface matched with the options
+    pub fn find(&self) -> Ft260Result<DeviceDescriptor> {
+        if let Some(interface) = self.interface {
+            if !(0..=1).contains(&interface) {
+                return Err(open_error(
+                    OpenErrorKind::WrongInterface,
+                    format!("interface {} should take `0` or `1`", interface),
+                ));
+            }
+        }
+        let mut found: Vec<DeviceDescriptor> = list_by_vid_pid(self.vendor_id, self.product_id)?
+            .into_iter()
+            .filter(|desc| self.path.as_ref().is_none_or(|p| *p == desc.path))
+            .filter(|desc| {
+                self.serial_number
+                    .as_ref()
+                    .is_none_or(|sn| Some(sn) == desc.serial_number.as_ref())
+            })
+            .collect();
+        if found.is_empty() {
+            return Err(open_error(
+                OpenErrorKind::NotFound,
+                format!("no device matched with {:?}", self),
+            ));
+        }
+        if let Some(interface) = self.interface {
+            found.retain(|desc| desc.interface == interface);
+            if found.is_empty() {
+                return Err(open_error(
+                    OpenErrorKind::WrongInterface,
+                    format!("device has no interface {}", interface),
+                ));
+            }
+        }
+        if self.index >= found.len() {
+            // out of index range
+            return Err(open_error(
+                OpenErrorKind::NotFound,
+                format!("only {} device(s) matched with {:?}", found.len(), self),
+            ));
+        }
+        Ok(found.swap_remove(self.index))
+    }
+
+    /// Find all HID interfaces of the chip which has the interface matched with the options.  
+    /// Interfaces are grouped by serial number, or by enumeration order if it is empty.
+    pub(crate) fn find_chip(&self) -> Ft260Result<Vec<DeviceDescriptor>> {
+        let desc = self.find()?;
+        let all = list_by_vid_pid(self.vendor_id, self.product_id)?;
+        let serial = desc.serial_number.clone().filter(|sn| !sn.is_empty());
+        let mut chip: Vec<DeviceDescriptor> = if serial.is_some() {
+            all.into_iter()
+                .filter(|d| d.serial_number == serial)
+                .collect()
+        } else {
+            // interface 0 and the following interface 1 should be on the same chip
+            let pos = all.iter().position(|d| d.path == desc.path).unwrap_or(0);
+            let first = if desc.interface == 1 && pos > 0 && all[pos - 1].interface == 0 {
+                pos - 1
+            } else {
+                pos
+            };
+            let last = if all[first].interface == 0
+                && first + 1 < all.len()
+                && all[first + 1].interface == 1
+            {
+                first + 1
+            } else {
+                first
+            };
+            all[first..=last].to_vec()
+        };
+        chip.sort_by_key(|d| d.interface);
+        Ok(chip)
+    }
+
+    /// Open the HID interface matched with the options
+    pub fn open(&self) -> Ft260Result<Device> {
+        let desc = self.find()?;
+        self.open_descriptor(&desc)
+    }
+
+    /// Open the HID interface described with `desc`
+    pub(crate) fn open_descriptor(&self, desc: &DeviceDescriptor) -> Ft260Result<Device> {
+        let transport = HidTransport::open(desc)?;
+        tracing::debug!(path = %desc.path, interface = desc.interface, "opened HID interface");
+        match self.reconnect {
+            None => Ok(Device::from_transport(transport)),
+            Some(timeout) => {
+                // the same chip is found by serial number if it has, as the path may change
+                let mut options = OpenOptions::new()
+                    .vid_pid(desc.vendor_id, desc.product_id)
+                    .interface(desc.interface);
+                options = match desc.serial_number.as_deref() {
+                    Some(sn) if !sn.is_empty() => options.serial_number(sn),
+                    _ => options.path(&desc.path),
+                };
+                // use the interface already opened for the first connection
+                let first = Mutex::new(Some(transport));
+                let transport = Reconnect::new(
+                    move || match first.lock().unwrap().take() {
+                        Some(t) => Ok(t),
+                        None => HidTransport::open(&options.find()?),
+                    },
+                    timeout,
+                );
+                Ok(Device::from_transport(transport))
+            }
+        }
+    }
+}
+
+/// Function of FT260 HID interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// I2C controller
+    I2c,
+    /// UART
+    Uart,
+}
+
+/// Description of FT260 HID interface found on USB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceDescriptor {
+    /// Platform-specific path of HID interface
+    pub path: String,
+    /// USB Vendor ID
+    pub vendor_id: u16,
+    /// USB Product ID
+    pub product_id: u16,
+    /// USB serial number string
+    pub serial_number: Option<String>,
+    /// USB manufacturer string
+    pub manufacturer: Option<String>,
+    /// USB product string
+    pub product: Option<String>,
+    /// HID interface number
+    pub interface: i32,
+    /// Device release number in BCD
+    pub release_number: u16,
+    /// Function of the interface guessed by its number.  
+    /// When `DCNF0` and `DCNF1` are the same level, interface `0` is I2C and `1` is UART.
+    /// In UART only mode (`DCNF1=1`, `DCNF0=0`) interface `0` is actually UART.
+    pub role: Role,
+}
+
+impl DeviceDescriptor {
+    fn from_info(inf: &DeviceInfo) -> Self {
+        Self {
+            path: inf.path().to_string_lossy().into_owned(),
+            vendor_id: inf.vendor_id(),
+            product_id: inf.product_id(),
+            serial_number: inf.serial_number().map(str::to_string),
+            manufacturer: inf.manufacturer_string().map(str::to_string),
+            product: inf.product_string().map(str::to_string),
+            interface: inf.interface_number(),
+            release_number: inf.release_number(),
+            role: if inf.interface_number() == 1 {
+                Role::Uart
+            } else {
+                Role::I2c
+            },
+        }
+    }
+}
+
+/// Enumerate HID interfaces of FT260 devices with default Vendor ID and Product ID.  
+/// Each FT260 chip has one or two interfaces, and a descriptor is returned for every one.
+pub fn list() -> Ft260Result<Vec<DeviceDescriptor>> {
+    list_by_vid_pid(VID_DEFAULT, PID_DEFAULT)
+}
+
+/// Enumerate HID interfaces of FT260 devices by explicit Vendor ID and Product ID.
+pub fn list_by_vid_pid(vendor_id: u16, product_id: u16) -> Ft260Result<Vec<DeviceDescriptor>> {
+    let api = hidapi::HidApi::new()?;
+    Ok(api
+        .device_list()
+        .filter(|inf| (inf.vendor_id(), inf.product_id()) == (vendor_id, product_id))
+        .map(DeviceDescriptor::from_info)
+        .collect())
+}
+
+impl std::fmt::Debug for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl std::fmt::Debug for Inner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Device")
+            .field("fifo", &self.fifo)
+            .field("reading", &self.reading)
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Device {
-    /// Create new `Device` instance from `hidapi::HidDevice` instance.  
-    /// When it is opened, a new thread is spawned and it continues to read HID input report from the device.
-    fn new(hid: hidapi::HidDevice) -> Self {
-        dbg!(&hid);
-        let mutex_hid = Arc::new(Mutex::new(hid));
-        let mutex_fifo = Arc::new(Mutex::new(ReportFifo::new()));
+    /// Create new `Device` instance on any `Transport` implementation.  
+    /// As same as a device opened by `open`, a new thread is spawned and it continues to read HID input report from the transport.
+    pub fn from_transport<T: Transport + 'static>(transport: T) -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(transport);
+        let shared_fifo = Arc::new(SharedFifo::new());
         let reading = Arc::new(AtomicBool::new(true));
+        let connected = Arc::new(AtomicBool::new(true));
+        let tap = Arc::new(Tap::default());
 
         let handle = thread::spawn({
-            let mutex_hid = mutex_hid.clone();
-            let mutex_fifo = mutex_fifo.clone();
+            let transport = transport.clone();
+            let shared_fifo = shared_fifo.clone();
             let reading = reading.clone();
-            print!("now starting a thread to read HID");
-            move || loop {
-                let mut buf = [0u8; 256];
-                let mut has_report = false;
-                if let Ok(hid) = mutex_hid.lock() {
-                    if let Ok(sz) = hid.read_timeout(&mut buf, 0) {
-                        has_report = sz > 0;
+            let connected = connected.clone();
+            let tap = tap.clone();
+            move || {
+                tracing::debug!("started reading input reports");
+                while reading.load(Ordering::Relaxed) {
+                    let mut buf = [0u8; 256];
+                    match transport.read_timeout(&mut buf, READ_TIMEOUT_MS) {
+                        Ok(sz) => {
+                            if !connected.swap(true, Ordering::Relaxed) {
+                                tracing::info!("reading input reports recovered");
+                            }
+                            if sz > 0 {
+                                tracing::trace!(report_id = buf[0], len = sz, "input report");
+                                tap.record(Direction::FromDevice, ReportType::Input, &buf[..sz]);
+                                shared_fifo
+                                    .push_report(buf.to_vec(), || reading.load(Ordering::Relaxed));
+                            }
+                        }
+                        Err(e) => {
+                            // the interface may be unplugged, so don't spin on it
+                            if connected.swap(false, Ordering::Relaxed) {
+                                tracing::warn!(error = %e, "reading input reports failed");
+                            }
+                            thread::sleep(READ_RETRY_INTERVAL);
+                        }
                     }
                 }
-                thread::yield_now();
-                if has_report {
-                    if let Ok(mut fifo) = mutex_fifo.lock() {
-                        fifo.push_report(buf.to_vec());
-                    }
-                }
-                thread::yield_now();
-                if !reading.load(Ordering::Relaxed) {
-                    print!("`reading` got to be `false`");
-                    return;
-                }
+                tracing::debug!("stopped reading input reports");
             }
         });
 
         Self {
-            hid: mutex_hid,
-            fifo: mutex_fifo,
-            reading,
-            handle,
+            inner: Arc::new(Inner {
+                transport,
+                fifo: shared_fifo,
+                reading,
+                connected,
+                handle: Some(handle),
+                i2c_lock: BusLock::new(),
+                uart_read_lock: BusLock::new(),
+                uart_write_lock: BusLock::new(),
+                gpio_lock: BusLock::new(),
+                pins: Mutex::new(PinRegistry::new()),
+                tap,
+            }),
         }
     }
 
-    /// Enumerate HID interfaces with specified conditions (VID, PID, IF#)  
-    /// If some were found, then create new `Device` instance from `hidapi::HidDevice` instance
-    fn try_new(vendor_id: u16, product_id: u16, interface: i32, index: usize) -> Option<Self> {
-        if interface < 0 || interface > 1 {
-            return None;
-        }
+    /// If the interface is connected.
+    /// It gets to be `false` when reading input report fails, e.g. by USB unplug,
+    /// and then operations fail with `Ft260Error::Disconnected`.
+    pub fn is_connected(&self) -> bool {
+        self.inner.connected.load(Ordering::Relaxed)
+    }
 
-        let api = hidapi::HidApi::new().expect("Failed to create HID API context");
-        let mut infs: Vec<&DeviceInfo> = Vec::new();
-        for inf in api.device_list() {
-            if (vendor_id, product_id, interface)
-                == (inf.vendor_id(), inf.product_id(), inf.interface_number())
-            {
-                print!("found:");
-                dbg!(inf);
-                infs.push(inf);
-            }
-        }
-        if index >= infs.len() {
-            // out of index range
-            None
-        } else {
-            if let Ok(hid) = infs[index].open_device(&api) {
-                print!("opened:");
-                dbg!(index);
-                Some(Self::new(hid))
-            } else {
-                None
+    /// Replace error from the transport with `Ft260Error::Disconnected` after the interface is lost
+    fn check_connection<T>(&self, res: Ft260Result<T>) -> Ft260Result<T> {
+        match res {
+            Err(Ft260Error::HidError { message }) if !self.is_connected() => {
+                Err(Ft260Error::Disconnected { message })
             }
+            res => res,
         }
     }
 
-    /// Exclusive reference to FIFO instance for HID input report from FT260 device
-    pub(crate) fn fifo<'a>(&'a self) -> MutexGuard<'a, ReportFifo> {
-        self.fifo.lock().unwrap()
+    /// FIFO instance for HID input report from FT260 device
+    pub(crate) fn fifo(&self) -> &SharedFifo {
+        &self.inner.fifo
+    }
+
+    /// Lock held during an I2C transaction not to interleave with ones from other threads
+    pub(crate) fn lock_i2c(&self) -> BusGuard<'_> {
+        self.inner.i2c_lock.lock()
+    }
+
+    /// Lock held during a UART read not to split data with ones from other threads
+    pub(crate) fn lock_uart_read(&self) -> BusGuard<'_> {
+        self.inner.uart_read_lock.lock()
+    }
+
+    /// Lock held during a UART write not to interleave data with ones from other threads
+    pub(crate) fn lock_uart_write(&self) -> BusGuard<'_> {
+        self.inner.uart_write_lock.lock()
+    }
+
+    /// Lock held during read-modify-write of GPIO report not to lose changes of other pins
+    pub(crate) fn lock_gpio(&self) -> BusGuard<'_> {
+        self.inner.gpio_lock.lock()
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn lock_i2c_async(&self) -> BusGuard<'_> {
+        self.inner.i2c_lock.lock_async().await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn lock_gpio_async(&self) -> BusGuard<'_> {
+        self.inner.gpio_lock.lock_async().await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn lock_uart_read_async(&self) -> BusGuard<'_> {
+        self.inner.uart_read_lock.lock_async().await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn lock_uart_write_async(&self) -> BusGuard<'_> {
+        self.inner.uart_write_lock.lock_async().await
+    }
+
+    /// Run blocking HID transfers on the blocking thread pool of tokio,
+    /// not to stall the executor thread of the caller.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn run_blocking<T, F>(&self, f: F) -> Ft260Result<T>
+    where
+        F: FnOnce(&Device) -> Ft260Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let device = self.clone();
+        // keep reports transferred in the span of the caller
+        let span = tracing::Span::current();
+        tokio::task::spawn_blocking(move || span.in_scope(|| f(&device)))
+            .await
+            .map_err(|e| Ft260Error::OtherError {
+                error: std::io::Error::other(e),
+            })?
+    }
+
+    /// Pop HID input report with a ID from FIFO, waiting for it until `deadline` without blocking the thread
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn wait_input_report_async(
+        &self,
+        id: u8,
+        deadline: Instant,
+    ) -> Option<Vec<u8>> {
+        self.inner.fifo.wait_report_async(id, deadline).await
+    }
+
+    /// Claim a GPIO group for a function until the claim is dropped.
+    /// Fails with `Ft260Error::PinConflict` if another function owns it.
+    pub(crate) fn claim_pins(&self, group: Group, owner: Owner) -> Ft260Result<PinClaim> {
+        let registry = self.inner.pins.lock().unwrap().clone();
+        registry.claim(group, owner)
+    }
+
+    /// Hold a GPIO group for a function until `release_pins`, regardless of instances using it.
+    /// Returns `false` if the function holds it already.
+    /// Fails with `Ft260Error::PinConflict` if another function owns it.
+    pub(crate) fn hold_pins(&self, group: Group, owner: Owner) -> Ft260Result<bool> {
+        self.inner.pins.lock().unwrap().hold(group, owner)
+    }
+
+    /// Release a GPIO group held by `hold_pins`
+    pub(crate) fn release_pins(&self, group: Group, owner: Owner) {
+        self.inner.pins.lock().unwrap().release(group, owner)
+    }
+
+    /// Edge of RI wakeup configured on the chip, shared by its interfaces
+    pub(crate) fn ri_wakeup(&self) -> Option<RiWakeup> {
+        self.inner.pins.lock().unwrap().ri_wakeup()
+    }
+
+    pub(crate) fn set_ri_wakeup(&self, edge: Option<RiWakeup>) {
+        self.inner.pins.lock().unwrap().set_ri_wakeup(edge)
+    }
+
+    /// Function owning a GPIO group, if any
+    pub(crate) fn pin_owner(&self, group: Group) -> Option<Owner> {
+        self.inner.pins.lock().unwrap().owner(group)
+    }
+
+    /// Track GPIO groups and wakeup configuration together with `other`,
+    /// which is an interface of the same chip.
+    /// Claims made before are kept in the former registry until dropped.
+    pub(crate) fn share_pins(&self, other: &Device) {
+        let registry = other.inner.pins.lock().unwrap().clone();
+        *self.inner.pins.lock().unwrap() = registry;
+    }
+
+    /// Start recording every report transferred on the interface into `capture`, labelled with `interface`.
+    /// Interfaces of a chip can share a capture, and a capture started already is replaced.
+    pub fn start_capture(&self, capture: &Arc<Capture>, interface: u8) {
+        self.inner.tap.set(Some((capture.clone(), interface)));
+    }
+
+    /// Stop recording reports started by `start_capture`
+    pub fn stop_capture(&self) {
+        self.inner.tap.set(None);
+    }
+
+    /// Set capacity and overflow policy of FIFO for a class of input reports.
+    /// `capacity` is at least `1`, and the default is 4096 reports with `OverflowPolicy::DropOldest`.
+    pub fn set_fifo_limit(&self, class: ReportClass, capacity: usize, policy: OverflowPolicy) {
+        self.inner
+            .fifo
+            .lock()
+            .set_limit(class.id(), capacity, policy);
+    }
+
+    /// Statistics of FIFO for a class of input reports
+    pub fn fifo_stats(&self, class: ReportClass) -> FifoStats {
+        self.inner.fifo.lock().stats(class.id())
+    }
+
+    /// Reset counters of FIFO for a class of input reports, and the high-water mark to the current length
+    pub fn reset_fifo_stats(&self, class: ReportClass) {
+        self.inner.fifo.lock().reset_stats(class.id());
+    }
+
+    /// Pop HID input report with a ID from FIFO, waiting for it until `deadline`
+    pub(crate) fn wait_input_report(&self, id: u8, deadline: Instant) -> Option<Vec<u8>> {
+        self.inner.fifo.wait_report(id, deadline)
     }
 
     /// Create instance to control GPIO features
     pub fn gpio(&self) -> Gpio {
-        Gpio::new(self)
+        Gpio::new(self.clone())
     }
 
     /// Create instance to control I2C features
     pub fn i2c(&self) -> I2c {
-        I2c::new(self)
+        I2c::new(self.clone())
     }
 
     /// Create instance to control UART features
     pub fn uart(&self) -> Uart {
-        Uart::new(self)
+        Uart::new(self.clone())
+    }
+
+    /// Create instance to receive interrupts on INTRIN, which are reported on the UART interface
+    pub fn interrupt(&self) -> Interrupt {
+        Interrupt::new(self.clone())
+    }
+
+    /// Create instance to query and configure USB suspend behaviour of the chip
+    pub fn power(&self) -> Power {
+        Power::new(self.clone())
+    }
+
+    /// Create instance to transfer raw HID reports, bypassing checks of `Gpio`, `I2c` and `Uart`
+    pub fn raw(&self) -> Raw {
+        Raw::new(self.clone())
+    }
+
+    /// Read and decode System Status feature report of the chip
+    pub fn system_status(&self) -> Ft260Result<SystemStatus> {
+        reports::ft260_get_system_status(self)
     }
 
-    /// Read input report manually
-    pub(crate) fn read_input(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
-        match self.hid.lock().unwrap().read_timeout(buf, timeout) {
-            Ok(sz) => Ft260Result::Ok(sz),
-            Err(e) => Ft260Result::Err(Ft260Error::from(e)),
+    /// Read and decode Chip Code feature report to identify the chip and its firmware
+    pub fn chip_info(&self) -> Ft260Result<ChipInfo> {
+        let code = reports::ft260_get_chip_code(self)?;
+        Ok(ChipInfo::from_hid(&code))
+    }
+
+    /// Get current system clock of the chip.
+    /// Only the clock is decoded from System Status, so that other fields can't fail it.
+    pub fn clock(&self) -> Ft260Result<Clock> {
+        reports::ft260_get_clock(self)
+    }
+
+    /// Set system clock of the chip.
+    /// Both I2C clock speed and UART baud rate are generated from it.
+    pub fn set_clock(&self, clock: Clock) -> Ft260Result<()> {
+        reports::ft260_set_clock(self, clock.to_hid_const())
+    }
+
+    /// Find the clock for `requested` rate according to `policy`
+    /// and switch to it if it is not the current one.
+    /// `None` if no clock `supports` the rate for `ClockPolicy::Lowest`,
+    /// while `ClockPolicy::Keep` only warns about it.
+    pub(crate) fn select_clock(
+        &self,
+        policy: ClockPolicy,
+        requested: u32,
+        supports: impl Fn(Clock) -> bool,
+    ) -> Ft260Result<Option<Rate>> {
+        let current = self.clock()?;
+        let selected = Rate::select(current, policy, requested, &supports);
+        if let Some(r) = selected {
+            if !supports(r.clock) {
+                tracing::warn!(
+                    clock = ?r.clock,
+                    requested = r.requested,
+                    "rate is out of the range supported with the current clock"
+                );
+            }
+            if r.clock != current {
+                self.set_clock(r.clock)?;
+            }
         }
+        Ok(selected)
     }
 
     /// Write HID output report manually
     pub(crate) fn write_output(&self, data: &[u8]) -> Ft260Result<()> {
-        match self.hid.lock().unwrap().write(data) {
-            Ok(_) => Ft260Result::Ok(()),
-            Err(e) => Ft260Result::Err(Ft260Error::from(e)),
-        }
+        tracing::trace!(report_id = data[0], len = data.len(), "output report");
+        self.inner
+            .tap
+            .record(Direction::ToDevice, ReportType::Output, data);
+        self.check_connection(self.inner.transport.write(data).map(|_| ()))
     }
 
     /// Read HID feature report
     pub(crate) fn get_feature(&self, buf: &mut [u8]) -> Ft260Result<usize> {
-        match self.hid.lock().unwrap().get_feature_report(buf) {
-            Ok(sz) => Ft260Result::Ok(sz),
-            Err(e) => Ft260Result::Err(Ft260Error::from(e)),
-        }
+        tracing::trace!(report_id = buf[0], "get feature report");
+        let sz = self.check_connection(self.inner.transport.get_feature_report(buf))?;
+        self.inner
+            .tap
+            .record(Direction::FromDevice, ReportType::Feature, &buf[..sz]);
+        Ok(sz)
     }
 
     /// Write HID feature report
     pub(crate) fn set_feature(&self, data: &[u8]) -> Ft260Result<()> {
-        match self.hid.lock().unwrap().send_feature_report(data) {
-            Ok(_) => Ft260Result::Ok(()),
-            Err(e) => Ft260Result::Err(Ft260Error::from(e)),
-        }
+        tracing::trace!(
+            report_id = data[0],
+            request = data.get(1).copied(),
+            "set feature report"
+        );
+        self.inner
+            .tap
+            .record(Direction::ToDevice, ReportType::Feature, data);
+        self.check_connection(self.inner.transport.send_feature_report(data))
     }
 }
 
-impl Drop for Device {
+impl Drop for Inner {
     fn drop(&mut self) {
-        print!("drop it:");
-        dbg!(&self);
-        // stop reading thread
+        // stop reading thread, which notices it within `READ_TIMEOUT_MS`
         self.reading.store(false, Ordering::Relaxed);
-        loop {
-            if self.handle.is_finished() {
-                println!("joined.");
-                return;
+        self.transport.shutdown();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                tracing::error!("thread reading input reports panicked");
             }
         }
     }
@@ -0,0 +1,69 @@
+use std::sync::{Condvar, Mutex};
+
+/// Lock to serialize transfers made of several reports, such as an I2C transaction.
+/// Unlike `std::sync::Mutex`, it can be acquired by async callers without blocking
+/// their executor thread, and the guard can be held across `.await`.
+#[derive(Debug, Default)]
+pub(crate) struct BusLock {
+    busy: Mutex<bool>,
+    released: Condvar,
+    #[cfg(feature = "tokio")]
+    released_async: tokio::sync::Notify,
+}
+
+/// Guard of `BusLock` releasing it on drop
+#[derive(Debug)]
+pub(crate) struct BusGuard<'a> {
+    lock: &'a BusLock,
+}
+
+impl BusLock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Block the current thread until the lock is acquired
+    pub fn lock(&self) -> BusGuard<'_> {
+        let mut busy = self.busy.lock().unwrap();
+        while *busy {
+            busy = self.released.wait(busy).unwrap();
+        }
+        *busy = true;
+        BusGuard { lock: self }
+    }
+
+    #[cfg(feature = "tokio")]
+    fn try_lock(&self) -> Option<BusGuard<'_>> {
+        let mut busy = self.busy.lock().unwrap();
+        if *busy {
+            None
+        } else {
+            *busy = true;
+            Some(BusGuard { lock: self })
+        }
+    }
+
+    /// Wait for the lock without blocking the executor thread
+    #[cfg(feature = "tokio")]
+    pub async fn lock_async(&self) -> BusGuard<'_> {
+        loop {
+            // register before trying, not to miss a release in between
+            let notified = self.released_async.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for BusGuard<'_> {
+    fn drop(&mut self) {
+        *self.lock.busy.lock().unwrap() = false;
+        self.lock.released.notify_one();
+        #[cfg(feature = "tokio")]
+        self.lock.released_async.notify_one();
+    }
+}
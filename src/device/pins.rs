@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::io::gpio::{Group, Owner};
+use crate::power::RiWakeup;
+use crate::{Ft260Error, Ft260Result};
+
+/// Function owning a GPIO group, with the ID of its `PinClaim` if it's bound to one
+#[derive(Debug, Clone, Copy)]
+struct Ownership {
+    owner: Owner,
+    claim: Option<u64>,
+}
+
+/// Functions owning GPIO groups of a chip
+#[derive(Debug, Default)]
+pub(crate) struct PinRegistry {
+    owners: Mutex<HashMap<Group, Ownership>>,
+    /// ID given to the next `PinClaim`
+    next_claim: AtomicU64,
+    /// edge of RI wakeup configured, which the chip doesn't report
+    ri_wakeup: Mutex<Option<RiWakeup>>,
+}
+
+/// Ownership of a GPIO group, released when dropped
+#[derive(Debug)]
+pub(crate) struct PinClaim {
+    registry: Arc<PinRegistry>,
+    group: Group,
+    id: u64,
+}
+
+impl PinRegistry {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn owner(&self, group: Group) -> Option<Owner> {
+        self.owners.lock().unwrap().get(&group).map(|o| o.owner)
+    }
+
+    /// Claim `group` for `owner`, failing if it's owned already
+    pub fn claim(self: &Arc<Self>, group: Group, owner: Owner) -> Ft260Result<PinClaim> {
+        let mut owners = self.owners.lock().unwrap();
+        if let Some(current) = owners.get(&group) {
+            return Err(Ft260Error::PinConflict {
+                group,
+                owner: current.owner,
+            });
+        }
+        let id = self.next_claim.fetch_add(1, Ordering::Relaxed);
+        owners.insert(
+            group,
+            Ownership {
+                owner,
+                claim: Some(id),
+            },
+        );
+        tracing::debug!(?group, ?owner, claim = id, "claimed pins");
+        Ok(PinClaim {
+            registry: self.clone(),
+            group,
+            id,
+        })
+    }
+
+    /// Hold `group` for `owner` until `release`, not bound to a `PinClaim`.
+    /// Returns `false` if `owner` holds it already, and fails if another function owns it.
+    pub fn hold(&self, group: Group, owner: Owner) -> Ft260Result<bool> {
+        let mut owners = self.owners.lock().unwrap();
+        match owners.get(&group) {
+            Some(current) if current.owner == owner && current.claim.is_none() => Ok(false),
+            Some(current) => Err(Ft260Error::PinConflict {
+                group,
+                owner: current.owner,
+            }),
+            None => {
+                owners.insert(group, Ownership { owner, claim: None });
+                tracing::debug!(?group, ?owner, "held pins");
+                Ok(true)
+            }
+        }
+    }
+
+    /// Release `group` held by `owner`, leaving it as is if another function owns it
+    pub fn release(&self, group: Group, owner: Owner) {
+        let mut owners = self.owners.lock().unwrap();
+        if owners
+            .get(&group)
+            .is_some_and(|o| o.owner == owner && o.claim.is_none())
+        {
+            owners.remove(&group);
+            tracing::debug!(?group, ?owner, "released pins");
+        }
+    }
+
+    pub fn ri_wakeup(&self) -> Option<RiWakeup> {
+        *self.ri_wakeup.lock().unwrap()
+    }
+
+    pub fn set_ri_wakeup(&self, edge: Option<RiWakeup>) {
+        *self.ri_wakeup.lock().unwrap() = edge;
+    }
+}
+
+impl PinClaim {
+    pub fn group(&self) -> Group {
+        self.group
+    }
+}
+
+impl Drop for PinClaim {
+    fn drop(&mut self) {
+        let mut owners = self.registry.owners.lock().unwrap();
+        if owners.get(&self.group).and_then(|o| o.claim) == Some(self.id) {
+            owners.remove(&self.group);
+            tracing::debug!(group = ?self.group, claim = self.id, "released pins");
+        }
+    }
+}
@@ -1,13 +1,135 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result};
 
-/// Common Error type in the crate
+use crate::chip::ChipMode;
+use crate::codec;
+use crate::device::Role;
+use crate::io::gpio::{Group, Owner};
+use crate::io::i2c::BusStatus;
+
+/// Reasons why opening FT260 device failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenErrorKind {
+    /// No HID interface matched with the conditions
+    NotFound,
+    /// Access to the HID interface was not permitted
+    PermissionDenied,
+    /// The HID interface has been opened already
+    AlreadyOpen,
+    /// The interface number was out of range or didn't match with the device found
+    WrongInterface,
+    /// Other errors reported by HID API
+    Other,
+}
+
+impl Display for OpenErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Self::NotFound => "not found",
+            Self::PermissionDenied => "permission denied",
+            Self::AlreadyOpen => "already open",
+            Self::WrongInterface => "wrong interface",
+            Self::Other => "other",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Reasons why an I2C transaction failed on the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I2cErrorKind {
+    /// The target didn't acknowledge its address
+    AddressNack,
+    /// The target didn't acknowledge a data byte
+    DataNack,
+    /// Another controller won the bus
+    ArbitrationLost,
+    /// The bus or the controller was busy
+    Busy,
+    /// Other errors reported by the controller
+    Other,
+}
+
+impl I2cErrorKind {
+    /// Decode the reason from the bus status.
+    /// `None` if the status has no error.
+    pub(crate) fn from_status(status: BusStatus) -> Option<Self> {
+        if status.contains(BusStatus::ArbitrationLost) {
+            Some(Self::ArbitrationLost)
+        } else if status.contains(BusStatus::AddressNack) {
+            Some(Self::AddressNack)
+        } else if status.contains(BusStatus::DataNack) {
+            Some(Self::DataNack)
+        } else if status.contains(BusStatus::Error) {
+            Some(Self::Other)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for I2cErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Self::AddressNack => "address NACK",
+            Self::DataNack => "data NACK",
+            Self::ArbitrationLost => "arbitration lost",
+            Self::Busy => "busy",
+            Self::Other => "bus error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Common Error type in the crate.
+/// More variants may be added, so matching it needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Ft260Error {
     /// Errors about HID
     HidError { message: String },
+    /// Errors in opening device
+    OpenError {
+        kind: OpenErrorKind,
+        message: String,
+    },
+    /// Device was disconnected from USB
+    Disconnected { message: String },
+    /// Function not available in the current chip mode
+    Unavailable { role: Role, mode: ChipMode },
     /// Errors in byte constant values in HID reports mainly
     ByteError { value: u8, message: String },
+    /// I2C transaction failed on the bus
+    I2cBusError {
+        /// Reason decoded from `status`
+        kind: I2cErrorKind,
+        /// Report ID of the step which failed: I2C Status (0xC0) if the bus was busy before the transaction,
+        /// I2C data reports (0xD0) for writing, or I2C Read Request (0xC2) for reading
+        report_id: u8,
+        /// Bus status read from the chip after the failure
+        status: BusStatus,
+        /// Target address
+        address: u8,
+        /// Bytes transferred before the failure
+        transferred: usize,
+    },
+    /// Data didn't arrive before the deadline
+    Timeout {
+        /// Report ID of the input reports waited for
+        report_id: u8,
+        /// Target address for I2C
+        address: Option<u8>,
+        /// Bytes transferred before the deadline
+        transferred: usize,
+        /// Bytes requested
+        requested: usize,
+    },
+    /// A report from the chip was not the one expected or malformed
+    UnexpectedReport { report_id: u8, message: String },
+    /// Argument out of the range accepted
+    InvalidArgument { message: String },
+    /// GPIO group is owned by another function
+    PinConflict { group: Group, owner: Owner },
     /// Errors in I2C communication
     I2cError { message: String },
     /// Errors in UART communication
@@ -22,9 +144,64 @@ impl Display for Ft260Error {
             Self::HidError { message } => {
                 write!(f, "ft260 error: {}", message)
             }
+            Self::OpenError { kind, message } => {
+                write!(f, "ft260 open error ({}): {}", kind, message)
+            }
+            Self::Disconnected { message } => {
+                write!(f, "ft260 disconnected: {}", message)
+            }
+            Self::Unavailable { role, mode } => {
+                write!(
+                    f,
+                    "ft260 {:?} is not available in chip mode {:?}",
+                    role, mode
+                )
+            }
             Self::ByteError { value, message } => {
                 write!(f, "byte data error - `{:#x}`: {}", value, message)
             }
+            Self::I2cBusError {
+                kind,
+                report_id,
+                status,
+                address,
+                transferred,
+            } => {
+                write!(
+                    f,
+                    "ft260 I2C {} at address {:#04x} after {} byte(s) for report {:#04x} (status {:#04x})",
+                    kind,
+                    address,
+                    transferred,
+                    report_id,
+                    status.bits()
+                )
+            }
+            Self::Timeout {
+                report_id,
+                address,
+                transferred,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "ft260 timed out with {} of {} byte(s) for report {:#04x}",
+                    transferred, requested, report_id
+                )?;
+                if let Some(addr) = address {
+                    write!(f, " from address {:#04x}", addr)?;
+                }
+                Ok(())
+            }
+            Self::UnexpectedReport { report_id, message } => {
+                write!(f, "ft260 unexpected report {:#04x}: {}", report_id, message)
+            }
+            Self::InvalidArgument { message } => {
+                write!(f, "ft260 invalid argument: {}", message)
+            }
+            Self::PinConflict { group, owner } => {
+                write!(f, "ft260 {:?} is owned by {:?}", group, owner)
+            }
             Self::I2cError { message } => {
                 write!(f, "ft260 I2C error: {}", message)
             }
@@ -40,8 +217,43 @@ impl Display for Ft260Error {
 
 impl Error for Ft260Error {}
 
+impl Ft260Error {
+    /// If the error means the HID interface has gone, e.g. by USB unplug
+    pub(crate) fn is_disconnection(&self) -> bool {
+        match self {
+            Self::Disconnected { .. } => true,
+            Self::OtherError { error } => matches!(
+                error.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::BrokenPipe
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl From<std::io::Error> for Ft260Error {
     fn from(e: std::io::Error) -> Self {
         Self::OtherError { error: e }
     }
 }
+
+impl From<codec::Error> for Ft260Error {
+    fn from(e: codec::Error) -> Self {
+        match e {
+            codec::Error::InvalidValue { value } => Self::ByteError {
+                value,
+                message: e.to_string(),
+            },
+            codec::Error::WrongReportId { report_id }
+            | codec::Error::ShortReport { report_id, .. } => Self::UnexpectedReport {
+                report_id,
+                message: e.to_string(),
+            },
+            codec::Error::BufferTooSmall { .. } | codec::Error::DataTooLong { .. } => {
+                Self::InvalidArgument {
+                    message: e.to_string(),
+                }
+            }
+        }
+    }
+}
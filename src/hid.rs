@@ -1,3 +1,5 @@
 pub(crate) mod consts;
+#[cfg(feature = "std")]
 pub(crate) mod rep_fifo;
+#[cfg(feature = "std")]
 pub(crate) mod reports;
@@ -1,9 +1,44 @@
 use std::collections::vec_deque::Iter;
 use std::collections::{HashMap, VecDeque};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::device::{FifoStats, OverflowPolicy};
+
+/// Default capacity of the queue for each report class
+pub(crate) const CAPACITY_DEFAULT: usize = 4096;
+
+/// Queue of reports in a class with its limit and statistics
+#[derive(Debug)]
+struct ClassQueue {
+    queue: VecDeque<Vec<u8>>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    received: u64,
+    dropped: u64,
+    high_water: usize,
+}
+
+impl ClassQueue {
+    fn new() -> Self {
+        ClassQueue {
+            queue: VecDeque::new(),
+            capacity: CAPACITY_DEFAULT,
+            policy: OverflowPolicy::DropOldest,
+            received: 0,
+            dropped: 0,
+            high_water: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.len() >= self.capacity
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct ReportFifo {
-    dict: HashMap<u8, VecDeque<Vec<u8>>>,
+    dict: HashMap<u8, ClassQueue>,
 }
 
 impl ReportFifo {
@@ -15,62 +50,102 @@ impl ReportFifo {
 
     pub fn clear_all(&mut self) {
         for (_, q) in self.dict.iter_mut() {
-            q.clear();
+            q.queue.clear();
         }
-        self.dict.clear();
     }
 
     const ID_MASK: u8 = 0xF0u8;
 
-    /// check if a key exists in dictionary or else insert new queue for the key
-    fn check_key(&mut self, k: u8) -> bool {
-        if self.dict.contains_key(&k) {
-            true
-        } else {
-            self.dict.insert(k, VecDeque::new());
-            false
-        }
+    /// queue for a key, inserted if it doesn't exist
+    fn queue(&mut self, k: u8) -> &mut ClassQueue {
+        self.dict.entry(k).or_insert_with(ClassQueue::new)
     }
 
     pub fn clear(&mut self, id: u8) {
         let k = id & Self::ID_MASK;
-        if self.check_key(k) {
-            self.dict.get_mut(&k).unwrap().clear();
+        self.queue(k).queue.clear();
+    }
+
+    /// push a report according to the overflow policy of its class.
+    /// The report is given back if it should wait for space with `Backpressure` policy.
+    pub fn push_report(&mut self, data: Vec<u8>) -> Result<(), Vec<u8>> {
+        let k = data[0] & Self::ID_MASK;
+        let q = self.queue(k);
+        if q.is_full() {
+            match q.policy {
+                OverflowPolicy::DropOldest => {
+                    while q.is_full() {
+                        q.queue.pop_front();
+                        q.dropped += 1;
+                    }
+                }
+                OverflowPolicy::DropNewest => {
+                    q.received += 1;
+                    q.dropped += 1;
+                    return Ok(());
+                }
+                OverflowPolicy::Backpressure => return Err(data),
+            }
         }
+        q.received += 1;
+        q.queue.push_back(data);
+        q.high_water = q.high_water.max(q.queue.len());
+        Ok(())
     }
 
-    pub fn push_report(&mut self, data: Vec<u8>) {
+    /// count a report given up to be pushed
+    pub fn drop_report(&mut self, data: &[u8]) {
         let k = data[0] & Self::ID_MASK;
-        self.check_key(k);
-        self.dict.get_mut(&k).unwrap().push_back(data);
+        let q = self.queue(k);
+        q.received += 1;
+        q.dropped += 1;
     }
 
     pub fn pop_report(&mut self, id: u8) -> Option<Vec<u8>> {
         let k = id & Self::ID_MASK;
-        if self.check_key(k) {
-            self.dict.get_mut(&k).unwrap().pop_front()
-        } else {
-            // queue has been just created
-            None
+        self.queue(k).queue.pop_front()
+    }
+
+    /// iterate and peek reports with a ID in queue
+    pub fn iter_peek(&mut self, id: u8) -> Iter<'_, Vec<u8>> {
+        let k = id & Self::ID_MASK;
+        self.queue(k).queue.iter()
+    }
+
+    /// set capacity and overflow policy of queue for reports with a ID.
+    /// Reports over the new capacity are dropped from the oldest.
+    pub fn set_limit(&mut self, id: u8, capacity: usize, policy: OverflowPolicy) {
+        let k = id & Self::ID_MASK;
+        let q = self.queue(k);
+        q.capacity = capacity.max(1);
+        q.policy = policy;
+        while q.queue.len() > q.capacity {
+            q.queue.pop_front();
+            q.dropped += 1;
         }
     }
 
-    /// number of reports with a ID in queue
-    pub fn len(&mut self, id: u8) -> usize {
+    /// statistics of queue for reports with a ID
+    pub fn stats(&mut self, id: u8) -> FifoStats {
         let k = id & Self::ID_MASK;
-        if self.check_key(k) {
-            self.dict[&k].len()
-        } else {
-            // queue has been just created
-            0
+        let q = self.queue(k);
+        FifoStats {
+            len: q.queue.len(),
+            capacity: q.capacity,
+            policy: q.policy,
+            received: q.received,
+            dropped: q.dropped,
+            high_water: q.high_water,
         }
     }
 
-    /// iterate and peek reports with a ID in queue
-    pub fn iter_peek(&mut self, id: u8) -> Iter<'_, Vec<u8>> {
+    /// reset counters of queue for reports with a ID, and high-water mark to the current length
+    pub fn reset_stats(&mut self, id: u8) {
         let k = id & Self::ID_MASK;
-        self.check_key(k);
-        self.dict[&k].iter()
+        let q = self.queue(k);
+        q.received = 0;
+        q.dropped = 0;
+        q.high_water = q.queue.len();
     }
 }
 
@@ -79,3 +154,109 @@ impl Drop for ReportFifo {
         self.clear_all();
     }
 }
+
+/// Interval to check if a push blocked by `Backpressure` policy should give up
+const BACKPRESSURE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// `ReportFifo` shared with the thread reading input reports.
+/// Waiters are signaled when a report arrives or leaves.
+#[derive(Debug)]
+pub(crate) struct SharedFifo {
+    fifo: Mutex<ReportFifo>,
+    changed: Condvar,
+    #[cfg(feature = "tokio")]
+    arrived: tokio::sync::Notify,
+}
+
+impl SharedFifo {
+    pub fn new() -> Self {
+        SharedFifo {
+            fifo: Mutex::new(ReportFifo::new()),
+            changed: Condvar::new(),
+            #[cfg(feature = "tokio")]
+            arrived: tokio::sync::Notify::new(),
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, ReportFifo> {
+        self.fifo.lock().unwrap()
+    }
+
+    /// push a report and wake up waiters.
+    /// With `Backpressure` policy it waits for space while `keep_waiting` returns `true`,
+    /// or the report is dropped.
+    pub fn push_report(&self, data: Vec<u8>, keep_waiting: impl Fn() -> bool) {
+        let mut fifo = self.lock();
+        let mut data = data;
+        loop {
+            match fifo.push_report(data) {
+                Ok(()) => break,
+                Err(d) if keep_waiting() => {
+                    data = d;
+                    fifo = self
+                        .changed
+                        .wait_timeout(fifo, BACKPRESSURE_CHECK_INTERVAL)
+                        .unwrap()
+                        .0;
+                }
+                Err(d) => {
+                    fifo.drop_report(&d);
+                    break;
+                }
+            }
+        }
+        drop(fifo);
+        self.changed.notify_all();
+        #[cfg(feature = "tokio")]
+        self.arrived.notify_waiters();
+    }
+
+    /// pop a report with a ID
+    pub fn pop_report(&self, id: u8) -> Option<Vec<u8>> {
+        let rep = self.lock().pop_report(id);
+        self.changed.notify_all();
+        rep
+    }
+
+    /// clear reports with a ID
+    pub fn clear(&self, id: u8) {
+        self.lock().clear(id);
+        self.changed.notify_all();
+    }
+
+    /// pop a report with a ID, waiting for it until `deadline`
+    pub fn wait_report(&self, id: u8, deadline: Instant) -> Option<Vec<u8>> {
+        let mut fifo = self.lock();
+        loop {
+            if let Some(rep) = fifo.pop_report(id) {
+                drop(fifo);
+                // a push may wait for space
+                self.changed.notify_all();
+                return Some(rep);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            fifo = self.changed.wait_timeout(fifo, deadline - now).unwrap().0;
+        }
+    }
+
+    /// pop a report with a ID, waiting for it until `deadline` as a task of tokio
+    #[cfg(feature = "tokio")]
+    pub async fn wait_report_async(&self, id: u8, deadline: Instant) -> Option<Vec<u8>> {
+        let deadline = tokio::time::Instant::from_std(deadline);
+        loop {
+            // register before popping, not to miss a report arriving in between
+            let notified = self.arrived.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(rep) = self.pop_report(id) {
+                return Some(rep);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+}
@@ -1,49 +1,42 @@
-use std::time::{Duration, Instant};
-
-use bitflags::Flags;
+use std::time::Instant;
 
+use crate::codec::{self, REPORT_LENGTH_MAX};
 use crate::device::Device;
 use crate::hid::consts::*;
-use crate::{Ft260Error, Ft260Result};
+use crate::Ft260Result;
 
-pub(crate) type Report = Option<Vec<u8>>;
+pub(crate) use crate::codec::system::{ChipCode, Clock, InterruptStatus, SystemStatus};
 
-pub(crate) type FeatureReportBuffer = [u8; 64];
+pub(crate) type Report = Option<Vec<u8>>;
 
-fn clear_input_report_queue_i2c(device: &Device) {
-    let id = ReportId::InOutI2cReport04 as u8;
-    device.fifo().clear(id);
-}
+pub(crate) type FeatureReportBuffer = [u8; REPORT_LENGTH_MAX];
 
-fn pop_input_report_i2c(device: &Device) -> Report {
+fn wait_input_report_i2c(device: &Device, deadline: Instant) -> Report {
     let id = ReportId::InOutI2cReport04 as u8;
-    device.fifo().pop_report(id)
+    device.wait_input_report(id, deadline)
 }
 
-fn get_input_reports_count_i2c(device: &Device) -> usize {
+#[cfg(feature = "tokio")]
+async fn wait_input_report_i2c_async(device: &Device, deadline: Instant) -> Report {
     let id = ReportId::InOutI2cReport04 as u8;
-    device.fifo().len(id)
-}
-
-fn clear_input_report_queue_uart(device: &Device) {
-    let id = ReportId::InOutUartReport04 as u8;
-    device.fifo().clear(id);
+    device.wait_input_report_async(id, deadline).await
 }
 
-fn pop_input_report_uart(device: &Device) -> Report {
+fn wait_input_report_uart(device: &Device, deadline: Instant) -> Report {
     let id = ReportId::InOutUartReport04 as u8;
-    device.fifo().pop_report(id)
+    device.wait_input_report(id, deadline)
 }
 
-fn get_input_reports_count_uart(device: &Device) -> usize {
+#[cfg(feature = "tokio")]
+async fn wait_input_report_uart_async(device: &Device, deadline: Instant) -> Report {
     let id = ReportId::InOutUartReport04 as u8;
-    device.fifo().len(id)
+    device.wait_input_report_async(id, deadline).await
 }
 
 fn get_input_report_byte_amount_uart(device: &Device) -> usize {
     let id = ReportId::InOutUartReport04 as u8;
     let mut amount = 0usize;
-    for rep in device.fifo().iter_peek(id) {
+    for rep in device.fifo().lock().iter_peek(id) {
         if rep.len() > 1 {
             amount += rep[1] as usize; // length value
         }
@@ -56,12 +49,29 @@ fn pop_input_report_int(device: &Device) -> Report {
     device.fifo().pop_report(id)
 }
 
+fn wait_input_report_int(device: &Device, deadline: Instant) -> Report {
+    let id = ReportId::InInterruptStatus as u8;
+    device.wait_input_report(id, deadline)
+}
+
+#[cfg(feature = "tokio")]
+async fn wait_input_report_int_async(device: &Device, deadline: Instant) -> Report {
+    let id = ReportId::InInterruptStatus as u8;
+    device.wait_input_report_async(id, deadline).await
+}
+
+fn ft260_set_request_params(device: &Device, request: Request, params: &[u8]) -> Ft260Result<()> {
+    let mut buf = feat_rep_buf();
+    let len = codec::system::encode_request(&mut buf, request, params)?;
+    device.set_feature(&buf[..len])
+}
+
 fn ft260_set_request(device: &Device, request: Request) -> Ft260Result<()> {
-    device.set_feature(&[ReportId::FeatSystemSetting as u8, request as u8])
+    ft260_set_request_params(device, request, &[])
 }
 
 fn ft260_set_request_u8(device: &Device, request: Request, value: u8) -> Ft260Result<()> {
-    device.set_feature(&[ReportId::FeatSystemSetting as u8, request as u8, value])
+    ft260_set_request_params(device, request, &[value])
 }
 
 fn ft260_set_request_u8x2(
@@ -70,50 +80,55 @@ fn ft260_set_request_u8x2(
     value1: u8,
     value2: u8,
 ) -> Ft260Result<()> {
-    device.set_feature(&[
-        ReportId::FeatSystemSetting as u8,
-        request as u8,
-        value1,
-        value2,
-    ])
+    ft260_set_request_params(device, request, &[value1, value2])
 }
 
 fn ft260_set_request_u16(device: &Device, request: Request, value: u16) -> Ft260Result<()> {
-    device.set_feature(&[
-        ReportId::FeatSystemSetting as u8,
-        request as u8,
-        ((value >> 0) & 0xFF) as u8,
-        ((value >> 8) & 0xFF) as u8,
-    ])
+    ft260_set_request_params(device, request, &value.to_le_bytes())
 }
 
 fn ft260_set_request_u32(device: &Device, request: Request, value: u32) -> Ft260Result<()> {
-    device.set_feature(&[
-        ReportId::FeatSystemSetting as u8,
-        request as u8,
-        ((value >> 0) & 0xFF) as u8,
-        ((value >> 8) & 0xFF) as u8,
-        ((value >> 16) & 0xFF) as u8,
-        ((value >> 24) & 0xFF) as u8,
-    ])
-}
-
-fn ft260_set_feature(device: &Device, data: &[u8]) -> Ft260Result<()> {
-    device.set_feature(data)
+    ft260_set_request_params(device, request, &value.to_le_bytes())
 }
 
 fn feat_rep_buf() -> FeatureReportBuffer {
-    [0u8; 64]
+    [0u8; REPORT_LENGTH_MAX]
 }
 
-fn ft260_get_feature(device: &Device, report_id: u8) -> Ft260Result<FeatureReportBuffer> {
-    let mut buf = [0u8; 64];
-    buf[0] = report_id;
-    let res = device.get_feature(&mut buf);
-    if let Ok(sz) = res {
-        Ok(buf)
-    } else {
-        Err(res.err().unwrap())
+/// Get feature report `id`, returning the buffer and the length of the report in it
+fn ft260_get_feature(device: &Device, id: ReportId) -> Ft260Result<(FeatureReportBuffer, usize)> {
+    let mut buf = feat_rep_buf();
+    buf[0] = id as u8;
+    let sz = device.get_feature(&mut buf)?;
+    Ok((buf, sz))
+}
+
+/// Record ID of the first data report sent or received on the span of the transfer,
+/// which has `report_id` field
+fn record_report_id(report_id: u8) {
+    tracing::Span::current().record("report_id", report_id);
+}
+
+/// Copy `decoded` data of input report `data` into `dst` as much as it can hold.
+/// Returns the number of bytes copied, which is `0` for a malformed report.
+fn copy_payload(
+    data: &[u8],
+    decoded: codec::CodecResult<&[u8]>,
+    dst: &mut [u8],
+) -> Ft260Result<usize> {
+    match decoded {
+        Ok(payload) => {
+            tracing::trace!(
+                report_id = data[0],
+                len = payload.len(),
+                "input report payload"
+            );
+            let sz_cpy = payload.len().min(dst.len());
+            dst[..sz_cpy].copy_from_slice(&payload[..sz_cpy]);
+            Ok(sz_cpy)
+        }
+        Err(e @ codec::Error::WrongReportId { .. }) => Err(e.into()),
+        Err(_) => Ok(0),
     }
 }
 
@@ -141,60 +156,78 @@ pub(crate) fn ft260_set_interrupt_trigger_type(
     )
 }
 
-pub(crate) fn ft260_get_chip_version(device: &Device) -> Ft260Result<u32> {
-    let mut buf = feat_rep_buf();
-    buf[0] = ReportId::FeatChipCode as u8;
-    let res = device.get_feature(&mut buf);
-    if let Ok(sz) = res {
-        if sz > 5 {
-            let ver = ((buf[1] as u32) << 0)
-                | ((buf[2] as u32) << 8)
-                | ((buf[3] as u32) << 16)
-                | ((buf[4] as u32) << 24);
-            Ok(ver)
-        } else {
-            Err(Ft260Error::HidError {
-                message: "Feature Report gotten is short".to_string(),
-            })
-        }
-    } else {
-        Err(res.err().unwrap())
-    }
+/// Get System Status feature report (0xA1)
+pub(crate) fn ft260_get_system_status(device: &Device) -> Ft260Result<SystemStatus> {
+    let (buf, sz) = ft260_get_feature(device, ReportId::FeatSystemSetting)?;
+    Ok(SystemStatus::decode(&buf[..sz])?)
+}
+
+/// Get system clock from System Status feature report (0xA1)
+pub(crate) fn ft260_get_clock(device: &Device) -> Ft260Result<Clock> {
+    let (buf, sz) = ft260_get_feature(device, ReportId::FeatSystemSetting)?;
+    Ok(SystemStatus::decode_clock(&buf[..sz])?)
+}
+
+/// Get Chip Code feature report (0xA0)
+pub(crate) fn ft260_get_chip_code(device: &Device) -> Ft260Result<ChipCode> {
+    let (buf, sz) = ft260_get_feature(device, ReportId::FeatChipCode)?;
+    Ok(ChipCode::decode(&buf[..sz])?)
 }
 
 pub(crate) mod i2c;
 
 pub(crate) mod uart;
 
-/*
-
-pub(crate) bool InterruptFlag = false;
-
-pub(crate) FT260_STATUS FT260_GetInterruptFlag(device: &Device, ref pbFlag: bool)
-{
-    do
-    {   // read all queued Interrupt Status report
-        let data = PopInputReportInt();
-        if (data == null) { break; }
-        if (data.Length >= 3 &&
-            data[0] == (byte)ReportId::InInterruptStatus &&
-            (data[1] & 1) != 0)
-        {
-            InterruptFlag = true;
+/// Wait until `deadline` for Interrupt Status report (0xB1) telling INTRIN was triggered,
+/// discarding the ones which don't
+pub(crate) fn ft260_wait_interrupt(device: &Device, deadline: Instant) -> Option<InterruptStatus> {
+    loop {
+        let data = wait_input_report_int(device, deadline)?;
+        if let Some(st) = decode_interrupt(&data) {
+            return Some(st);
         }
-    } while (true);
-    pbFlag = InterruptFlag;
-    return FT260_STATUS.OK;
+    }
 }
 
-pub(crate) FT260_STATUS FT260_CleanInterruptFlag(device: &Device, ref pbFlag: bool)
-{
-    let res = FT260_GetInterruptFlag(hid, ref pbFlag);
-    InterruptFlag = false; // clear
-    return res;
+/// Async version of `ft260_wait_interrupt`
+#[cfg(feature = "tokio")]
+pub(crate) async fn ft260_wait_interrupt_async(
+    device: &Device,
+    deadline: Instant,
+) -> Option<InterruptStatus> {
+    loop {
+        let data = wait_input_report_int_async(device, deadline).await?;
+        if let Some(st) = decode_interrupt(&data) {
+            return Some(st);
+        }
+    }
 }
 
+/// Take Interrupt Status report telling INTRIN was triggered if it's pending
+pub(crate) fn ft260_pop_interrupt(device: &Device) -> Option<InterruptStatus> {
+    while let Some(data) = pop_input_report_int(device) {
+        if let Some(st) = decode_interrupt(&data) {
+            return Some(st);
+        }
+    }
+    None
+}
 
-// */
+/// Discard pending Interrupt Status reports
+pub(crate) fn ft260_clear_interrupt(device: &Device) {
+    let id = ReportId::InInterruptStatus as u8;
+    device.fifo().clear(id);
+}
+
+fn decode_interrupt(data: &[u8]) -> Option<InterruptStatus> {
+    match InterruptStatus::decode(data) {
+        Ok(st) if st.intrin => Some(st),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::debug!(error = %e, "malformed interrupt status report");
+            None
+        }
+    }
+}
 
 pub(crate) mod gpio;
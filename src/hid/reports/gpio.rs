@@ -1,7 +1,8 @@
+use crate::codec::gpio::GpioReport;
 use crate::device::Device;
 use crate::hid::consts::*;
 use crate::hid::reports::*;
-use crate::{Ft260Error, Ft260Result};
+use crate::Ft260Result;
 
 pub(crate) fn select_gpio_2_function(
     device: &Device,
@@ -31,6 +32,13 @@ pub(crate) fn set_suspend_out_polarity(
     ft260_set_request_u8(device, Request::SetSuspendOutPol, polarity as u8)
 }
 
+/// Set levels driven while USB is suspended, for GPIO0-5 and GPIOA-H.
+/// Each value has a mask of pins to drive in the low byte and their levels in the high byte.
+pub(crate) fn set_suspend_mode(device: &Device, gpio_0: u16, gpio_a: u16) -> Ft260Result<()> {
+    ft260_set_request_u16(device, Request::SetSuspendModeGpio0, gpio_0)?;
+    ft260_set_request_u16(device, Request::SetSuspendModeGpioA, gpio_a)
+}
+
 pub(crate) fn set_i2c_pins(device: &Device, enable: I2cEnableMode) -> Ft260Result<()> {
     ft260_set_request_u8(device, Request::SetI2cMode, enable as u8)
 }
@@ -43,110 +51,31 @@ pub(crate) fn set_dcd_ri_pins(device: &Device, enable: UartDcdRiEnableMode) -> F
     ft260_set_request_u8(device, Request::EnableUartDcdRi, enable as u8)
 }
 
-#[derive(Clone, Copy)]
-struct GpioRequest {
-    /// GPIO 0-5 pin value
-    pub val: GpioBitVal,
-    /// GPIO 0-5 direction (0: input, 1: output)
-    pub dir: GpioBitVal,
-    /// GPIO A-H pin value
-    pub ex_val: GpioExBitVal,
-    /// GPIO A-H direction (0: input, 1: output)
-    pub ex_dir: GpioExBitVal,
-}
-
 /// 4.7.1 GPIO Write Request
-fn set(device: &Device, report: GpioRequest) -> Ft260Result<()> {
-    ft260_set_feature(
-        device,
-        &[
-            ReportId::FeatGpio as u8,
-            report.val.bits(),
-            report.dir.bits(),
-            report.ex_val.bits(),
-            report.ex_dir.bits(),
-        ],
-    )
+fn set(device: &Device, report: GpioReport) -> Ft260Result<()> {
+    let mut buf = feat_rep_buf();
+    let len = report.encode(&mut buf)?;
+    device.set_feature(&buf[..len])
 }
 
 /// 4.7.2 GPIO Read Request
-fn get(device: &Device) -> Ft260Result<GpioRequest> {
-    let rid = ReportId::FeatGpio as u8;
-    let res = ft260_get_feature(device, rid);
-    if let Ok(report) = res {
-        assert_eq!(rid, report[0]);
-        Ok(GpioRequest {
-            val: GpioBitVal::from_bits(report[1]).unwrap(),
-            dir: GpioBitVal::from_bits(report[2]).unwrap(),
-            ex_val: GpioExBitVal::from_bits(report[3]).unwrap(),
-            ex_dir: GpioExBitVal::from_bits(report[4]).unwrap(),
-        })
-    } else {
-        Err(res.err().unwrap())
-    }
-}
-
-fn pin_to_bits(pin: GpioPinNum) -> (GpioBitVal, GpioExBitVal) {
-    let mut bits: GpioBitVal = GpioBitVal::None;
-    if pin.contains(GpioPinNum::GPIO_0) {
-        bits |= GpioBitVal::_0;
-    }
-    if pin.contains(GpioPinNum::GPIO_1) {
-        bits |= GpioBitVal::_1;
-    }
-    if pin.contains(GpioPinNum::GPIO_2) {
-        bits |= GpioBitVal::_2;
-    }
-    if pin.contains(GpioPinNum::GPIO_3) {
-        bits |= GpioBitVal::_3;
-    }
-    if pin.contains(GpioPinNum::GPIO_4) {
-        bits |= GpioBitVal::_4;
-    }
-    if pin.contains(GpioPinNum::GPIO_5) {
-        bits |= GpioBitVal::_5;
-    }
-    let mut ex_bits: GpioExBitVal = GpioExBitVal::None;
-    if pin.contains(GpioPinNum::GPIO_A) {
-        ex_bits |= GpioExBitVal::_A;
-    }
-    if pin.contains(GpioPinNum::GPIO_B) {
-        ex_bits |= GpioExBitVal::_B;
-    }
-    if pin.contains(GpioPinNum::GPIO_C) {
-        ex_bits |= GpioExBitVal::_C;
-    }
-    if pin.contains(GpioPinNum::GPIO_D) {
-        ex_bits |= GpioExBitVal::_D;
-    }
-    if pin.contains(GpioPinNum::GPIO_E) {
-        ex_bits |= GpioExBitVal::_E;
-    }
-    if pin.contains(GpioPinNum::GPIO_F) {
-        ex_bits |= GpioExBitVal::_F;
-    }
-    if pin.contains(GpioPinNum::GPIO_G) {
-        ex_bits |= GpioExBitVal::_G;
-    }
-    if pin.contains(GpioPinNum::GPIO_H) {
-        ex_bits |= GpioExBitVal::_H;
-    }
-
-    (bits, ex_bits)
+fn get(device: &Device) -> Ft260Result<GpioReport> {
+    let (buf, sz) = ft260_get_feature(device, ReportId::FeatGpio)?;
+    Ok(GpioReport::decode(&buf[..sz])?)
 }
 
+/// Change direction of a pin by read-modify-write of GPIO report.
+/// The caller should hold `Device::lock_gpio` not to lose changes of others, as well as `write`.
 pub(crate) fn set_dir(device: &Device, pin: GpioPinNum, dir: GpioDir) -> Ft260Result<()> {
     let res = get(device);
     if let Ok(req) = res {
         let mut req = req;
-        let (bit, ex_bit) = pin_to_bits(pin);
         // 0: input, 1: output
         let bit_dir = match dir {
             GpioDir::In => false,
             GpioDir::Out => true,
         };
-        req.dir.set(bit, bit_dir);
-        req.ex_dir.set(ex_bit, bit_dir);
+        req.dir.set(pin, bit_dir);
         set(device, req)
     } else {
         Err(res.err().unwrap())
@@ -156,8 +85,7 @@ pub(crate) fn set_dir(device: &Device, pin: GpioPinNum, dir: GpioDir) -> Ft260Re
 pub(crate) fn read(device: &Device, pin: GpioPinNum) -> Ft260Result<GpioValue> {
     let res = get(device);
     if let Ok(req) = res {
-        let (bit, ex_bit) = pin_to_bits(pin);
-        if req.val.intersects(bit) || req.ex_val.intersects(ex_bit) {
+        if req.val.intersects(pin) {
             Ok(GpioValue::High)
         } else {
             Ok(GpioValue::Low)
@@ -171,13 +99,11 @@ pub(crate) fn write(device: &Device, pin: GpioPinNum, val: GpioValue) -> Ft260Re
     let res = get(device);
     if let Ok(req) = res {
         let mut req = req;
-        let (bit, ex_bit) = pin_to_bits(pin);
         let bit_val = match val {
             GpioValue::Low => false,
             GpioValue::High => true,
         };
-        req.val.set(bit, bit_val);
-        req.ex_val.set(ex_bit, bit_val);
+        req.val.set(pin, bit_val);
         set(device, req)
     } else {
         Err(res.err().unwrap())
@@ -0,0 +1,110 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::device::{self, DeviceDescriptor};
+use crate::Ft260Result;
+
+/// Default interval to enumerate HID interfaces
+pub const INTERVAL_DEFAULT: Duration = Duration::from_millis(500);
+
+/// Change of FT260 HID interfaces on USB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The interface was plugged in
+    Arrived(DeviceDescriptor),
+    /// The interface was unplugged
+    Removed(DeviceDescriptor),
+}
+
+/// Watcher of FT260 HID interfaces plugged in and unplugged.
+/// HID interfaces are enumerated periodically in a thread, and the changes are reported as `Event`.
+/// Interfaces already plugged in when it starts are reported as `Event::Arrived` at first.
+/// The thread stops when the watcher is dropped.
+///
+/// ```no_run
+/// use ft260hid::device::{PID_DEFAULT, VID_DEFAULT};
+/// use ft260hid::hotplug::{Event, Watcher, INTERVAL_DEFAULT};
+///
+/// let (_watcher, events) = Watcher::channel(VID_DEFAULT, PID_DEFAULT, INTERVAL_DEFAULT);
+/// for event in events {
+///     match event {
+///         Event::Arrived(desc) => println!("arrived: {}", desc.path),
+///         Event::Removed(desc) => println!("removed: {}", desc.path),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Watcher {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Watch interfaces of Vendor ID and Product ID, and call `callback` with each event in the watcher thread
+    pub fn new<F>(vendor_id: u16, product_id: u16, interval: Duration, callback: F) -> Self
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        Self::with_enumerator(
+            interval,
+            move || device::list_by_vid_pid(vendor_id, product_id),
+            callback,
+        )
+    }
+
+    /// Watch interfaces of Vendor ID and Product ID, and send events to the channel returned
+    pub fn channel(vendor_id: u16, product_id: u16, interval: Duration) -> (Self, Receiver<Event>) {
+        let (tx, rx) = mpsc::channel();
+        let watcher = Self::new(vendor_id, product_id, interval, move |event| {
+            let _ = tx.send(event);
+        });
+        (watcher, rx)
+    }
+
+    /// Watch interfaces listed by `enumerate` instead of `hidapi`.
+    /// Enumeration failed is skipped, and interfaces are identified by `DeviceDescriptor::path`.
+    pub fn with_enumerator<E, F>(interval: Duration, mut enumerate: E, mut callback: F) -> Self
+    where
+        E: FnMut() -> Ft260Result<Vec<DeviceDescriptor>> + Send + 'static,
+        F: FnMut(Event) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            let mut present: Vec<DeviceDescriptor> = Vec::new();
+            loop {
+                if let Ok(list) = enumerate() {
+                    for desc in present.iter() {
+                        if !list.iter().any(|d| d.path == desc.path) {
+                            callback(Event::Removed(desc.clone()));
+                        }
+                    }
+                    for desc in list.iter() {
+                        if !present.iter().any(|d| d.path == desc.path) {
+                            callback(Event::Arrived(desc.clone()));
+                        }
+                    }
+                    present = list;
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => return,
+                }
+            }
+        });
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // disconnecting the channel wakes the thread up
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
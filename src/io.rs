@@ -2,5 +2,77 @@
 pub mod gpio;
 /// submodule as interface to use I2C feature
 pub mod i2c;
+/// submodule as interface to receive interrupts on INTRIN
+pub mod interrupt;
 /// submodule as interface to use UART feature
 pub mod uart;
+
+use std::time::Instant;
+
+use tracing::Span;
+
+use crate::Ft260Result;
+
+/// State of `I2c`, `Uart` or `Interrupt` before initialization, which can't transfer data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uninitialized;
+
+/// State of `I2c`, `Uart` or `Interrupt` after initialization, which can transfer data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Initialized;
+
+/// Initialization state of `I2c`, `Uart` and `Interrupt`, either `Uninitialized` or `Initialized`
+pub trait State: sealed::Sealed + std::fmt::Debug + Send + Sync + 'static {
+    /// If the function needs to be reset when the interface is dropped
+    #[doc(hidden)]
+    const INITIALIZED: bool;
+}
+
+impl State for Uninitialized {
+    const INITIALIZED: bool = false;
+}
+
+impl State for Initialized {
+    const INITIALIZED: bool = true;
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Uninitialized {}
+    impl Sealed for super::Initialized {}
+}
+
+/// Run a transfer in `span`, recording the bytes transferred, how long it took and its failure.
+/// `span` should have `bytes` and `elapsed_us` fields,
+/// and `report_id` field for the data report chosen by `hid::reports`.
+fn traced(span: Span, f: impl FnOnce() -> Ft260Result<usize>) -> Ft260Result<usize> {
+    let _enter = span.enter();
+    let start = Instant::now();
+    let res = f();
+    record(&span, start, &res);
+    res
+}
+
+/// Async version of `traced`
+#[cfg(feature = "tokio")]
+async fn traced_async(
+    span: Span,
+    f: impl std::future::Future<Output = Ft260Result<usize>>,
+) -> Ft260Result<usize> {
+    use tracing::Instrument;
+    let start = Instant::now();
+    let res = f.instrument(span.clone()).await;
+    let _enter = span.enter();
+    record(&span, start, &res);
+    res
+}
+
+fn record(span: &Span, start: Instant, res: &Ft260Result<usize>) {
+    if let Ok(bytes) = res {
+        span.record("bytes", bytes);
+    }
+    span.record("elapsed_us", start.elapsed().as_micros() as u64);
+    if let Err(e) = res {
+        tracing::debug!(error = %e, "transfer failed");
+    }
+}
@@ -1,12 +1,17 @@
-use crate::device::Device;
+use std::sync::{Arc, Mutex};
+
+use crate::device::{Device, PinClaim};
 use crate::hid::consts::*;
 use crate::hid::reports;
-use crate::Ft260Result;
+use crate::{Ft260Error, Ft260Result};
 
 /// Interface type to use GPIO function of the FT260 device.
+/// Groups enabled by `enable_pin` are owned by this instance until `disable_pin` or drop,
+/// and I2C or UART can't take them meanwhile.
 #[derive(Debug)]
-pub struct Gpio<'a> {
-    device: &'a Device,
+pub struct Gpio {
+    device: Device,
+    claims: Arc<Mutex<Vec<PinClaim>>>,
 }
 
 /// FT260 has 14 GPIO pins (Refer "3.3 Pin Description" in [datasheet](https://ftdichip.com/wp-content/uploads/2023/11/DS_FT260.pdf))
@@ -43,7 +48,7 @@ pub enum Pin {
 }
 
 /// GPIO groups separated by functions
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Group {
     /// Pins for I2C
     Gpio_0_1,
@@ -61,6 +66,67 @@ pub enum Group {
     Gpio_G,
 }
 
+/// Function owning a GPIO group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Owner {
+    /// `I2c` initialized on `Group::Gpio_0_1`
+    I2c,
+    /// `Uart` initialized on `Group::Gpio_B_C_D_E_F_H`
+    Uart,
+    /// `Interrupt` initialized on `Group::Gpio_3`
+    Interrupt,
+    /// USB remote wakeup enabled by `Power` on `Group::Gpio_3` (WAKEUP) or `Group::Gpio_4_5` (RI),
+    /// until it's disabled
+    Wakeup,
+    /// `Gpio` which enabled the group
+    Gpio,
+}
+
+impl Pin {
+    /// Group the pin belongs to
+    pub fn group(self) -> Group {
+        match self {
+            Pin::Gpio0 | Pin::Gpio1 => Group::Gpio_0_1,
+            Pin::Gpio2 => Group::Gpio_2,
+            Pin::Gpio3 => Group::Gpio_3,
+            Pin::Gpio4 | Pin::Gpio5 => Group::Gpio_4_5,
+            Pin::GpioA => Group::Gpio_A,
+            Pin::GpioB | Pin::GpioC | Pin::GpioD | Pin::GpioE | Pin::GpioF | Pin::GpioH => {
+                Group::Gpio_B_C_D_E_F_H
+            }
+            Pin::GpioG => Group::Gpio_G,
+        }
+    }
+}
+
+/// Switch pins of a group to GPIO function regardless of their owner
+pub(crate) fn enable(device: &Device, group: Group) -> Ft260Result<()> {
+    match group {
+        Group::Gpio_0_1 => reports::gpio::set_i2c_pins(device, I2cEnableMode::Disabled),
+        Group::Gpio_2 => reports::gpio::select_gpio_2_function(device, Gpio2Function::Gpio),
+        Group::Gpio_3 => reports::ft260_set_wakeup_interrupt(device, WakeupIntEnableMode::Disabled),
+        Group::Gpio_4_5 => reports::gpio::set_dcd_ri_pins(device, UartDcdRiEnableMode::Disabled),
+        Group::Gpio_A => reports::gpio::select_gpio_a_function(device, GpioAFunction::Gpio),
+        Group::Gpio_B_C_D_E_F_H => reports::gpio::set_uart_pins(device, UartEnableMode::Off),
+        Group::Gpio_G => reports::gpio::select_gpio_g_function(device, GpioGFunction::Gpio),
+    }
+}
+
+/// Reset pins of a group to their default function regardless of their owner
+pub(crate) fn disable(device: &Device, group: Group) -> Ft260Result<()> {
+    match group {
+        Group::Gpio_0_1 => reports::gpio::set_i2c_pins(device, I2cEnableMode::Enabled),
+        Group::Gpio_2 => reports::gpio::select_gpio_2_function(device, Gpio2Function::SuspOut),
+        Group::Gpio_3 => reports::ft260_set_wakeup_interrupt(device, WakeupIntEnableMode::Enabled),
+        Group::Gpio_4_5 => reports::gpio::set_dcd_ri_pins(device, UartDcdRiEnableMode::Enabled),
+        Group::Gpio_A => reports::gpio::select_gpio_a_function(device, GpioAFunction::TxActive),
+        Group::Gpio_B_C_D_E_F_H => {
+            reports::gpio::set_uart_pins(device, UartEnableMode::NoFlowControl)
+        }
+        Group::Gpio_G => reports::gpio::select_gpio_g_function(device, GpioGFunction::BcdDet),
+    }
+}
+
 /// Direction of GPIO pin
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Dir {
@@ -79,62 +145,75 @@ pub enum Val {
     High,
 }
 
-impl<'a> Gpio<'a> {
-    pub(crate) fn new(device: &'a Device) -> Self {
-        Self { device }
+impl Gpio {
+    pub(crate) fn new(device: Device) -> Self {
+        Self {
+            device,
+            claims: Arc::new(Mutex::new(Vec::new())),
+        }
     }
 
-    /// Enable GPIO function for specific pin group
+    /// Enable GPIO function for specific pin group.
+    /// Fails with `Ft260Error::PinConflict` if I2C, UART or another `Gpio` owns the group.
     pub fn enable_pin(&self, group: Group) -> Ft260Result<()> {
-        match group {
-            Group::Gpio_0_1 => reports::gpio::set_i2c_pins(self.device, I2cEnableMode::Disabled),
-            Group::Gpio_2 => {
-                reports::gpio::select_gpio_2_function(self.device, Gpio2Function::Gpio)
-            }
-            Group::Gpio_3 => {
-                reports::ft260_set_wakeup_interrupt(self.device, WakeupIntEnableMode::Disabled)
-            }
-            Group::Gpio_4_5 => {
-                reports::gpio::set_dcd_ri_pins(self.device, UartDcdRiEnableMode::Disabled)
-            }
-            Group::Gpio_A => {
-                reports::gpio::select_gpio_a_function(self.device, GpioAFunction::Gpio)
-            }
-            Group::Gpio_B_C_D_E_F_H => {
-                reports::gpio::set_uart_pins(self.device, UartEnableMode::Off)
-            }
-            Group::Gpio_G => {
-                reports::gpio::select_gpio_g_function(self.device, GpioGFunction::Gpio)
-            }
-        }
+        let mut claims = self.claims.lock().unwrap();
+        let claim = if claims.iter().any(|c| c.group() == group) {
+            None
+        } else {
+            Some(self.device.claim_pins(group, Owner::Gpio)?)
+        };
+        enable(&self.device, group)?;
+        claims.extend(claim);
+        Ok(())
     }
 
-    /// Disable GPIO function and reset as default function
+    /// Disable GPIO function and reset as default function.
+    /// Fails with `Ft260Error::PinConflict` if the group is owned by others.
     pub fn disable_pin(&self, group: Group) -> Ft260Result<()> {
-        match group {
-            Group::Gpio_0_1 => reports::gpio::set_i2c_pins(self.device, I2cEnableMode::Enabled),
-            Group::Gpio_2 => {
-                reports::gpio::select_gpio_2_function(self.device, Gpio2Function::SuspOut)
-            }
-            Group::Gpio_3 => {
-                reports::ft260_set_wakeup_interrupt(self.device, WakeupIntEnableMode::Enabled)
-            }
-            Group::Gpio_4_5 => {
-                reports::gpio::set_dcd_ri_pins(self.device, UartDcdRiEnableMode::Enabled)
-            }
-            Group::Gpio_A => {
-                reports::gpio::select_gpio_a_function(self.device, GpioAFunction::TxActive)
-            }
-            Group::Gpio_B_C_D_E_F_H => {
-                reports::gpio::set_uart_pins(self.device, UartEnableMode::NoFlowControl)
-            }
-            Group::Gpio_G => {
-                reports::gpio::select_gpio_g_function(self.device, GpioGFunction::BcdDet)
+        let mut claims = self.claims.lock().unwrap();
+        if !claims.iter().any(|c| c.group() == group) {
+            if let Some(owner) = self.device.pin_owner(group) {
+                return Err(Ft260Error::PinConflict { group, owner });
             }
         }
+        disable(&self.device, group)?;
+        claims.retain(|c| c.group() != group);
+        Ok(())
+    }
+
+    /// Function owning a GPIO group, if any
+    pub fn owner(&self, group: Group) -> Option<Owner> {
+        self.device.pin_owner(group)
+    }
+
+    /// Fail if the pin is owned by a function other than this `Gpio`
+    fn check_owner(&self, pin: Pin) -> Ft260Result<()> {
+        let group = pin.group();
+        if self
+            .claims
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.group() == group)
+        {
+            return Ok(());
+        }
+        match self.device.pin_owner(group) {
+            Some(owner) => Err(Ft260Error::PinConflict { group, owner }),
+            None => Ok(()),
+        }
+    }
+
+    /// Another handle sharing the groups owned by this one
+    #[cfg(feature = "tokio")]
+    fn share(&self) -> Self {
+        Self {
+            device: self.device.clone(),
+            claims: self.claims.clone(),
+        }
     }
 
-    fn pin_to_num(pin: Pin) -> GpioPinNum {
+    pub(crate) fn pin_to_num(pin: Pin) -> GpioPinNum {
         match pin {
             Pin::Gpio0 => GpioPinNum::GPIO_0,
             Pin::Gpio1 => GpioPinNum::GPIO_1,
@@ -153,29 +232,49 @@ impl<'a> Gpio<'a> {
         }
     }
 
-    /// Set direction of GPIO
-    pub fn set_dir(&self, pin_sel: Pin, dir: Dir) -> Ft260Result<()> {
-        let dir = match dir {
+    fn dir_to_hid(dir: Dir) -> GpioDir {
+        match dir {
             Dir::In => GpioDir::In,
             Dir::Out => GpioDir::Out,
-        };
-        reports::gpio::set_dir(self.device, Self::pin_to_num(pin_sel), dir)
+        }
     }
 
-    /// Set output value from GPIO pin
-    pub fn write(&self, pin_sel: Pin, val_out: Val) -> Ft260Result<()> {
-        let val_out = match val_out {
+    fn val_to_hid(val: Val) -> GpioValue {
+        match val {
             Val::Low => GpioValue::Low,
             Val::High => GpioValue::High,
-        };
-        reports::gpio::write(self.device, Self::pin_to_num(pin_sel), val_out)
+        }
+    }
+
+    /// Fails with `Ft260Error::PinConflict` if the pin is owned by I2C, UART, interrupt, wakeup function or another `Gpio`,
+    /// Fails with `Ft260Error::PinConflict` if the pin is owned by I2C, UART, interrupt or wakeup function,
+    /// as well as `write`, `set_pull_up`, `set_pull_down` and `set_open_drain`.
+    pub fn set_dir(&self, pin_sel: Pin, dir: Dir) -> Ft260Result<()> {
+        self.check_owner(pin_sel)?;
+        let _lock = self.device.lock_gpio();
+        reports::gpio::set_dir(
+            &self.device,
+            Self::pin_to_num(pin_sel),
+            Self::dir_to_hid(dir),
+        )
+    }
+
+    /// Set output value from GPIO pin
+    pub fn write(&self, pin_sel: Pin, val_out: Val) -> Ft260Result<()> {
+        self.check_owner(pin_sel)?;
+        let _lock = self.device.lock_gpio();
+        reports::gpio::write(
+            &self.device,
+            Self::pin_to_num(pin_sel),
+            Self::val_to_hid(val_out),
+        )
     }
 
     /// Get input/output value of GPIO pin
     pub fn read(&self, pin_sel: Pin) -> Ft260Result<Val> {
         let pin_sel = Self::pin_to_num(pin_sel);
 
-        let res = reports::gpio::read(self.device, pin_sel);
+        let res = reports::gpio::read(&self.device, pin_sel);
         if let Ok(val) = res {
             Ok(match val {
                 GpioValue::Low => Val::Low,
@@ -187,7 +286,8 @@ impl<'a> Gpio<'a> {
     }
 
     fn set_pin_params(&self, pin_sel: Pin, req: Request) -> Ft260Result<()> {
-        reports::gpio::set_pin_params(self.device, Self::pin_to_num(pin_sel), req)
+        self.check_owner(pin_sel)?;
+        reports::gpio::set_pin_params(&self.device, Self::pin_to_num(pin_sel), req)
     }
 
     /// Set pull-up
@@ -205,3 +305,75 @@ impl<'a> Gpio<'a> {
         self.set_pin_params(pin_sel, Request::SetGpioOpenDrain)
     }
 }
+
+/// Async API on tokio runtime.
+/// GPIO is controlled by feature reports, which are transferred on the blocking thread pool.
+/// Changes of direction and value wait for the GPIO lock without blocking the executor thread.
+#[cfg(feature = "tokio")]
+impl Gpio {
+    /// Enable GPIO function for specific pin group asynchronously
+    pub async fn enable_pin_async(&self, group: Group) -> Ft260Result<()> {
+        let gpio = self.share();
+        self.device
+            .run_blocking(move |_| gpio.enable_pin(group))
+            .await
+    }
+
+    /// Disable GPIO function and reset as default function asynchronously
+    pub async fn disable_pin_async(&self, group: Group) -> Ft260Result<()> {
+        let gpio = self.share();
+        self.device
+            .run_blocking(move |_| gpio.disable_pin(group))
+            .await
+    }
+
+    /// Set direction of GPIO asynchronously
+    pub async fn set_dir_async(&self, pin_sel: Pin, dir: Dir) -> Ft260Result<()> {
+        self.check_owner(pin_sel)?;
+        let (pin, dir) = (Self::pin_to_num(pin_sel), Self::dir_to_hid(dir));
+        let _lock = self.device.lock_gpio_async().await;
+        self.device
+            .run_blocking(move |device| reports::gpio::set_dir(device, pin, dir))
+            .await
+    }
+
+    /// Set output value from GPIO pin asynchronously
+    pub async fn write_async(&self, pin_sel: Pin, val_out: Val) -> Ft260Result<()> {
+        self.check_owner(pin_sel)?;
+        let (pin, val) = (Self::pin_to_num(pin_sel), Self::val_to_hid(val_out));
+        let _lock = self.device.lock_gpio_async().await;
+        self.device
+            .run_blocking(move |device| reports::gpio::write(device, pin, val))
+            .await
+    }
+
+    /// Get input/output value of GPIO pin asynchronously
+    pub async fn read_async(&self, pin_sel: Pin) -> Ft260Result<Val> {
+        let gpio = self.share();
+        self.device.run_blocking(move |_| gpio.read(pin_sel)).await
+    }
+
+    /// Set pull-up asynchronously
+    pub async fn set_pull_up_async(&self, pin_sel: Pin) -> Ft260Result<()> {
+        let gpio = self.share();
+        self.device
+            .run_blocking(move |_| gpio.set_pull_up(pin_sel))
+            .await
+    }
+
+    /// Set pull-down asynchronously
+    pub async fn set_pull_down_async(&self, pin_sel: Pin) -> Ft260Result<()> {
+        let gpio = self.share();
+        self.device
+            .run_blocking(move |_| gpio.set_pull_down(pin_sel))
+            .await
+    }
+
+    /// Configure pins for open-drain output asynchronously
+    pub async fn set_open_drain_async(&self, pin_sel: Pin) -> Ft260Result<()> {
+        let gpio = self.share();
+        self.device
+            .run_blocking(move |_| gpio.set_open_drain(pin_sel))
+            .await
+    }
+}
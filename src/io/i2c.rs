@@ -1,15 +1,52 @@
+use std::marker::PhantomData;
 use std::time::Duration;
 
+use tracing::field::Empty;
+
+use crate::chip::{ClockPolicy, Rate};
+use crate::device::{Device, PinClaim};
 use crate::hid::consts::*;
 use crate::hid::reports;
-use crate::io::gpio;
-use crate::{device::Device, Ft260Error, Ft260Result};
+use crate::io::{self, gpio, Initialized, State, Uninitialized};
+use crate::{Ft260Error, Ft260Result, I2cErrorKind};
+
+/// Status of I2C controller and bus read from the chip
+pub use crate::codec::i2c::I2cBusStatus as BusStatus;
 
 /// Interface type to use I2C function of the FT2260 device.
+/// It's `Uninitialized` when created, and `init` turns it into `I2c<Initialized>`,
+/// which is the only state with methods to transfer data.
+/// Dropping the initialized instance resets the I2C controller of the chip,
+/// and a failure of the reset is only logged instead of panicking.
+///
+/// ```
+/// use ft260hid::device::Device;
+/// use ft260hid::io::i2c;
+/// use ft260hid::sim::{Eeprom, Simulator};
+///
+/// let sim = Simulator::new();
+/// sim.attach_i2c(0x50, Eeprom::at24c02());
+/// let dev = Device::from_transport(sim.interface(0));
+/// let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+/// assert_eq!(i2c.write(0x50, i2c::Flag::StartAndStop, &[0, 1], 2).unwrap(), 2);
+/// ```
+///
+/// Transfer before initialization doesn't compile.
+///
+/// ```compile_fail
+/// # use ft260hid::device::Device;
+/// # use ft260hid::io::i2c;
+/// # use ft260hid::sim::Simulator;
+/// # let sim = Simulator::new();
+/// let dev = Device::from_transport(sim.interface(0));
+/// dev.i2c().write(0x50, i2c::Flag::StartAndStop, &[0, 1], 2);
+/// ```
 #[derive(Debug)]
-pub struct I2c<'a> {
-    device: &'a Device,
-    inited: bool,
+pub struct I2c<S: State = Uninitialized> {
+    device: Device,
+    /// ownership of I2C pins while initialized
+    _pins: Option<PinClaim>,
+    state: PhantomData<S>,
 }
 
 /// Flags to indicate I2C bus conditions
@@ -35,30 +72,150 @@ pub const KBPS_DEFAULT: u16 = 100;
 /// Default timeout duration
 pub const DURATION_WAIT_DEFAULT: Duration = Duration::from_millis(5000);
 
-impl<'a> I2c<'a> {
+/// Maximum 7-bit target address
+const ADDRESS_MAX: u8 = 0x7F;
+
+/// Fail if the bus is not ready to start a transaction
+fn check_idle(status: BusStatus, address: u8) -> Ft260Result<()> {
+    if status.intersects(BusStatus::ControllerBusy | BusStatus::BusBusy)
+        || !status.contains(BusStatus::ControllerIdle)
+    {
+        return Err(Ft260Error::I2cBusError {
+            kind: I2cErrorKind::Busy,
+            report_id: ReportId::FeatI2cStatus as u8,
+            status,
+            address,
+            transferred: 0,
+        });
+    }
+    Ok(())
+}
+
+/// Fail if the bus status tells the last operation by `report_id` failed.
+/// Other bits are invalid while the controller is busy, so it passes then.
+fn check_error(
+    report_id: ReportId,
+    status: BusStatus,
+    address: u8,
+    transferred: usize,
+) -> Ft260Result<()> {
+    if status.contains(BusStatus::ControllerBusy) {
+        return Ok(());
+    }
+    match I2cErrorKind::from_status(status) {
+        Some(kind) => Err(Ft260Error::I2cBusError {
+            kind,
+            report_id: report_id as u8,
+            status,
+            address,
+            transferred,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Error for data read less than requested, which is a bus error if the status tells or a timeout
+fn short_read_error(
+    status: BusStatus,
+    address: u8,
+    transferred: usize,
+    requested: usize,
+) -> Ft260Error {
+    match check_error(ReportId::OutI2cReadRequest, status, address, transferred) {
+        Err(e) => e,
+        Ok(()) => Ft260Error::Timeout {
+            report_id: ReportId::InOutI2cReport04 as u8,
+            address: Some(address),
+            transferred,
+            requested,
+        },
+    }
+}
+
+impl I2c<Uninitialized> {
     /// create a new `I2c` instance
-    pub(crate) fn new(device: &'a Device) -> Self {
+    pub(crate) fn new(device: Device) -> Self {
         Self {
             device,
-            inited: false,
+            _pins: None,
+            state: PhantomData,
         }
     }
 
-    /// Initialize I2C function with clock speed
-    pub fn init(&mut self, kbps: u16) -> Ft260Result<()> {
-        let gpio = self.device.gpio();
-        if let Err(e) = gpio.disable_pin(gpio::Group::Gpio_0_1) {
-            dbg!(&e);
+    /// Initialize I2C function with clock speed.
+    /// The I2C pins are owned by the initialized instance until it's dropped,
+    /// and it fails with `Ft260Error::PinConflict` if GPIO owns them.
+    /// The current system clock is kept, and the speed is passed to the chip as it is.
+    pub fn init(self, kbps: u16) -> Ft260Result<I2c<Initialized>> {
+        self.init_with_clock(kbps, ClockPolicy::Keep)
+            .map(|(i2c, _)| i2c)
+    }
+
+    /// Initialize I2C function with clock speed, choosing the system clock according to `policy`.
+    /// Returns the clock selected with the speed passed to the chip.
+    /// Fails if no clock supports the speed for `ClockPolicy::Lowest`.
+    pub fn init_with_clock(
+        self,
+        kbps: u16,
+        policy: ClockPolicy,
+    ) -> Ft260Result<(I2c<Initialized>, Rate)> {
+        let pins = self
+            .device
+            .claim_pins(gpio::Group::Gpio_0_1, gpio::Owner::I2c)?;
+        let rate = self
+            .device
+            .select_clock(policy, kbps as u32, |clock| clock.supports_i2c_kbps(kbps))?
+            .ok_or_else(|| Ft260Error::I2cError {
+                message: format!(
+                    "Clock speed {} kbps is not supported with clock policy {:?}",
+                    kbps, policy
+                ),
+            })?;
+        if let Err(e) = gpio::disable(&self.device, gpio::Group::Gpio_0_1) {
+            tracing::debug!(error = %e, "failed to disable GPIO 0 and 1");
             return Err(e);
         }
-        match reports::i2c::init(&self.device, kbps) {
-            Ok(()) => {
-                self.inited = true;
-                Ok(())
-            }
-            Err(e) => Err(e),
+        reports::i2c::init(&self.device, kbps)?;
+        tracing::debug!(kbps, clock = ?rate.clock, "initialized I2C");
+        let i2c = I2c {
+            device: self.device.clone(),
+            _pins: Some(pins),
+            state: PhantomData,
+        };
+        Ok((i2c, rate))
+    }
+}
+
+impl<S: State> I2c<S> {
+    /// Status of I2C controller and bus
+    pub fn status(&self) -> Ft260Result<BusStatus> {
+        reports::i2c::get_status(&self.device)
+    }
+
+    /// If I2C bus is idling or busy
+    pub fn is_idle(&self) -> Option<bool> {
+        match reports::i2c::get_status(&self.device) {
+            Ok(s) => Some(s == BusStatus::ControllerIdle),
+            Err(_) => None,
         }
     }
+}
+
+impl I2c<Initialized> {
+    /// Fail if arguments are out of range
+    fn check_args(&self, addr: u8, buf_len: usize, len: usize) -> Ft260Result<()> {
+        if addr > ADDRESS_MAX {
+            return Err(Ft260Error::InvalidArgument {
+                message: format!("I2C address {:#04x} is not 7-bit", addr),
+            });
+        }
+        if len > buf_len {
+            return Err(Ft260Error::InvalidArgument {
+                message: format!("length {} exceeds buffer of {} byte(s)", len, buf_len),
+            });
+        }
+        Ok(())
+    }
 
     fn flag_to_cond(f: Flag) -> I2cCondition {
         match f {
@@ -71,7 +228,8 @@ impl<'a> I2c<'a> {
         }
     }
 
-    /// Read I2C data
+    /// Read I2C data.
+    /// Returns the number of bytes read until `duration_wait` elapses.
     pub fn read(
         &self,
         addr: u8,
@@ -80,24 +238,51 @@ impl<'a> I2c<'a> {
         len: usize,
         duration_wait: Duration,
     ) -> Ft260Result<usize> {
-        assert!(self.inited);
-        reports::i2c::read(
-            self.device,
+        self.check_args(addr, buf.len(), len)?;
+        let _lock = self.device.lock_i2c();
+        let span = tracing::debug_span!(
+            "i2c_read",
             addr,
-            Self::flag_to_cond(flag),
-            buf,
+            ?flag,
             len,
-            duration_wait,
-        )
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        io::traced(span, || {
+            reports::i2c::read(
+                &self.device,
+                addr,
+                Self::flag_to_cond(flag),
+                buf,
+                len,
+                duration_wait,
+            )
+        })
     }
 
     /// Write I2C data
     pub fn write(&self, addr: u8, flag: Flag, buf: &[u8], len: usize) -> Ft260Result<usize> {
-        assert!(self.inited);
-        reports::i2c::write(self.device, addr, Self::flag_to_cond(flag), buf, len)
+        self.check_args(addr, buf.len(), len)?;
+        let _lock = self.device.lock_i2c();
+        let span = tracing::debug_span!(
+            "i2c_write",
+            addr,
+            ?flag,
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        io::traced(span, || {
+            reports::i2c::write(&self.device, addr, Self::flag_to_cond(flag), buf, len)
+        })
     }
 
-    /// Write and read I2C data
+    /// Write and read I2C data.
+    /// Other threads sharing the device can't interrupt the transaction.
+    /// Fails with `Ft260Error::I2cBusError` if the bus is busy or the target doesn't respond,
+    /// and with `Ft260Error::Timeout` if data doesn't arrive within `duration_wait`.
     pub fn write_read(
         &self,
         addr: u8,
@@ -107,82 +292,204 @@ impl<'a> I2c<'a> {
         len_read: usize,
         duration_wait: Duration,
     ) -> Ft260Result<()> {
-        assert!(self.inited);
-        match reports::i2c::get_status(self.device) {
-            Ok(s) => {
-                if s != I2cBusStatus::ControllerIdle {
-                    return Err(Ft260Error::I2cError {
-                        message: "Bus was not in idle".to_string(),
-                    });
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
-        match self.write(addr, Flag::Start, buf_write, len_write) {
-            Ok(bytes_written) => {
-                if bytes_written != len_write {
-                    return Err(Ft260Error::I2cError {
-                        message: "Failed to write all data".to_string(),
-                    });
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
-        match reports::i2c::get_status(self.device) {
-            Ok(s) => {
-                if s.intersects(I2cBusStatus::AddressNack) || s.intersects(I2cBusStatus::DataNack) {
-                    return Err(Ft260Error::I2cError {
-                        message: "Device returned NACK".to_string(),
-                    });
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        };
-        match self.read(
+        self.check_args(addr, buf_write.len(), len_write)?;
+        self.check_args(addr, buf_read.len(), len_read)?;
+        let _lock = self.device.lock_i2c();
+        let span = tracing::debug_span!(
+            "i2c_write_read",
             addr,
-            Flag::ReStartAndStop,
-            buf_read,
+            len_write,
             len_read,
-            duration_wait,
-        ) {
-            Ok(bytes_read) => {
-                if bytes_read != len_read {
-                    return Err(Ft260Error::I2cError {
-                        message: "Failed to read all data".to_string(),
-                    });
-                }
-            }
-            Err(e) => {
-                return Err(e);
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        io::traced(span, || {
+            check_idle(reports::i2c::get_status(&self.device)?, addr)?;
+            let bytes_written = reports::i2c::write(
+                &self.device,
+                addr,
+                I2cCondition::Start,
+                buf_write,
+                len_write,
+            )?;
+            check_error(
+                ReportId::InOutI2cReport04,
+                reports::i2c::get_status(&self.device)?,
+                addr,
+                bytes_written,
+            )?;
+            // the read has a span of its own for the data report it receives
+            let bytes_read = tracing::debug_span!("read", report_id = Empty).in_scope(|| {
+                reports::i2c::read(
+                    &self.device,
+                    addr,
+                    I2cCondition::ReStartAndStop,
+                    buf_read,
+                    len_read,
+                    duration_wait,
+                )
+            })?;
+            if bytes_read != len_read {
+                let status = reports::i2c::get_status(&self.device)?;
+                return Err(short_read_error(status, addr, bytes_read, len_read));
             }
-        }
-        Ok(())
+            Ok(len_read)
+        })
+        .map(|_| ())
     }
+}
 
-    /// If I2C bus is idling or busy
-    pub fn is_idle(&self) -> Option<bool> {
-        match reports::i2c::get_status(self.device) {
-            Ok(s) => Some(s == I2cBusStatus::ControllerIdle),
-            Err(e) => None,
+/// Async API on tokio runtime.
+/// Waiting for input reports doesn't block the executor thread,
+/// and other HID transfers run on the blocking thread pool.
+#[cfg(feature = "tokio")]
+impl<S: State> I2c<S> {
+    /// Status of I2C controller and bus, asynchronously
+    pub async fn status_async(&self) -> Ft260Result<BusStatus> {
+        self.device.run_blocking(reports::i2c::get_status).await
+    }
+
+    /// If I2C bus is idling or busy, asynchronously
+    pub async fn is_idle_async(&self) -> Option<bool> {
+        match self.status_async().await {
+            Ok(s) => Some(s == BusStatus::ControllerIdle),
+            Err(_) => None,
         }
     }
+}
+
+#[cfg(feature = "tokio")]
+impl I2c<Initialized> {
+    /// Read I2C data asynchronously
+    pub async fn read_async(
+        &self,
+        addr: u8,
+        flag: Flag,
+        buf: &mut [u8],
+        len: usize,
+        duration_wait: Duration,
+    ) -> Ft260Result<usize> {
+        self.check_args(addr, buf.len(), len)?;
+        let _lock = self.device.lock_i2c_async().await;
+        let span = tracing::debug_span!(
+            "i2c_read",
+            addr,
+            ?flag,
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        let read = reports::i2c::read_async(
+            &self.device,
+            addr,
+            Self::flag_to_cond(flag),
+            buf,
+            len,
+            duration_wait,
+        );
+        io::traced_async(span, read).await
+    }
+
+    /// Write I2C data asynchronously
+    pub async fn write_async(
+        &self,
+        addr: u8,
+        flag: Flag,
+        buf: &[u8],
+        len: usize,
+    ) -> Ft260Result<usize> {
+        self.check_args(addr, buf.len(), len)?;
+        let _lock = self.device.lock_i2c_async().await;
+        let span = tracing::debug_span!(
+            "i2c_write",
+            addr,
+            ?flag,
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        let write = self.write_blocking(addr, Self::flag_to_cond(flag), &buf[..len]);
+        io::traced_async(span, write).await
+    }
+
+    /// Write and read I2C data asynchronously.
+    /// Other threads and tasks sharing the device can't interrupt the transaction.
+    /// It fails in the same way as `write_read`.
+    pub async fn write_read_async(
+        &self,
+        addr: u8,
+        buf_write: &[u8],
+        len_write: usize,
+        buf_read: &mut [u8],
+        len_read: usize,
+        duration_wait: Duration,
+    ) -> Ft260Result<()> {
+        self.check_args(addr, buf_write.len(), len_write)?;
+        self.check_args(addr, buf_read.len(), len_read)?;
+        let _lock = self.device.lock_i2c_async().await;
+        let span = tracing::debug_span!(
+            "i2c_write_read",
+            addr,
+            len_write,
+            len_read,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        use tracing::Instrument;
+        let transaction = async {
+            check_idle(self.status_async().await?, addr)?;
+            let bytes_written = self
+                .write_blocking(addr, I2cCondition::Start, &buf_write[..len_write])
+                .await?;
+            check_error(
+                ReportId::InOutI2cReport04,
+                self.status_async().await?,
+                addr,
+                bytes_written,
+            )?;
+            // the read has a span of its own for the data report it receives
+            let bytes_read = reports::i2c::read_async(
+                &self.device,
+                addr,
+                I2cCondition::ReStartAndStop,
+                buf_read,
+                len_read,
+                duration_wait,
+            )
+            .instrument(tracing::debug_span!("read", report_id = Empty))
+            .await?;
+            if bytes_read != len_read {
+                let status = self.status_async().await?;
+                return Err(short_read_error(status, addr, bytes_read, len_read));
+            }
+            Ok(len_read)
+        };
+        io::traced_async(span, transaction).await.map(|_| ())
+    }
 
-    fn reset(&self) -> Ft260Result<()> {
-        reports::i2c::reset(self.device)
+    async fn write_blocking(
+        &self,
+        addr: u8,
+        cond: I2cCondition,
+        data: &[u8],
+    ) -> Ft260Result<usize> {
+        let data = data.to_vec();
+        self.device
+            .run_blocking(move |device| reports::i2c::write(device, addr, cond, &data, data.len()))
+            .await
     }
 }
 
-impl<'a> Drop for I2c<'a> {
+impl<S: State> Drop for I2c<S> {
     fn drop(&mut self) {
-        if self.inited {
-            self.reset()
-                .expect("I2C master should be reset successfully")
+        if S::INITIALIZED {
+            if let Err(e) = reports::i2c::reset(&self.device) {
+                tracing::warn!(error = %e, "failed to reset I2C controller");
+            }
         }
     }
 }
@@ -0,0 +1,230 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub use crate::chip::{InterruptDuration, InterruptTrigger};
+use crate::codec::system::InterruptStatus;
+use crate::device::{Device, PinClaim};
+use crate::hid::consts::{UartInterruptStatus, WakeupIntEnableMode};
+use crate::hid::reports;
+use crate::io::{gpio, Initialized, State, Uninitialized};
+use crate::Ft260Result;
+
+/// Interval for a listener thread to check if it should stop
+const LISTEN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Interrupt triggered on INTRIN (GPIO3), taken from Interrupt Status report (0xB1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// Level of UART DCD reported with the interrupt
+    pub dcd: bool,
+    /// Level of UART RI reported with the interrupt
+    pub ri: bool,
+}
+
+impl Event {
+    fn from_hid(st: &InterruptStatus) -> Self {
+        Self {
+            dcd: st.uart.contains(UartInterruptStatus::Dcd),
+            ri: st.uart.contains(UartInterruptStatus::Ri),
+        }
+    }
+}
+
+/// Interface type to receive interrupts on INTRIN (GPIO3) of the FT260 device.
+/// It's `Uninitialized` when created, and `init` turns it into `Interrupt<Initialized>`,
+/// which configures the trigger and receives events.
+///
+/// The chip reports interrupts on the UART interface.
+/// Each event is received by only one of `wait`, `try_wait` and the listeners,
+/// and events of UART DCD or RI without INTRIN are discarded.
+///
+/// ```
+/// use std::time::Duration;
+/// use ft260hid::device::Device;
+/// use ft260hid::io::interrupt::{InterruptDuration, InterruptTrigger};
+/// use ft260hid::sim::Simulator;
+///
+/// let sim = Simulator::new();
+/// let dev = Device::from_transport(sim.interface(1));
+/// let int = dev
+///     .interrupt()
+///     .init(InterruptTrigger::Falling, InterruptDuration::Ms1)
+///     .unwrap();
+/// sim.set_intrin(true);
+/// sim.set_intrin(false);
+/// assert!(int.wait(Duration::from_secs(1)).is_some());
+/// ```
+#[derive(Debug)]
+pub struct Interrupt<S: State = Uninitialized> {
+    device: Device,
+    /// ownership of GPIO3 while initialized
+    _pins: Option<PinClaim>,
+    state: PhantomData<S>,
+}
+
+impl Interrupt<Uninitialized> {
+    pub(crate) fn new(device: Device) -> Self {
+        Self {
+            device,
+            _pins: None,
+            state: PhantomData,
+        }
+    }
+
+    /// Switch GPIO3 to INTRIN and trigger interrupts by `trigger`.
+    /// `duration` is the time a level should be kept to trigger, and it's ignored for edges.
+    /// GPIO3 is owned by the initialized instance until it's dropped, which switches it back to GPIO,
+    /// and it fails with `Ft260Error::PinConflict` if GPIO or wakeup function owns it.
+    pub fn init(
+        self,
+        trigger: InterruptTrigger,
+        duration: InterruptDuration,
+    ) -> Ft260Result<Interrupt<Initialized>> {
+        let device = &self.device;
+        let pins = device.claim_pins(gpio::Group::Gpio_3, gpio::Owner::Interrupt)?;
+        if let Err(e) = reports::ft260_set_wakeup_interrupt(device, WakeupIntEnableMode::Enabled) {
+            tracing::debug!(error = %e, "failed to enable INTRIN");
+            return Err(e);
+        }
+        let int = Interrupt {
+            device: self.device.clone(),
+            _pins: Some(pins),
+            state: PhantomData,
+        };
+        int.set_trigger(trigger, duration)?;
+        tracing::debug!(?trigger, ?duration, "initialized interrupt");
+        Ok(int)
+    }
+}
+
+impl<S: State> Interrupt<S> {
+    /// Get current trigger condition, with the level duration which is `None` for edges
+    pub fn trigger(&self) -> Ft260Result<(InterruptTrigger, Option<InterruptDuration>)> {
+        let st = self.device.system_status()?;
+        Ok((st.interrupt_trigger, st.interrupt_duration))
+    }
+}
+
+impl Interrupt<Initialized> {
+    /// Change the trigger condition, discarding interrupts pending
+    pub fn set_trigger(
+        &self,
+        trigger: InterruptTrigger,
+        duration: InterruptDuration,
+    ) -> Ft260Result<()> {
+        reports::ft260_set_interrupt_trigger_type(
+            &self.device,
+            trigger.to_hid_const(),
+            duration.to_hid_const(),
+        )
+        .inspect_err(|e| tracing::debug!(error = %e, "failed to set interrupt trigger"))?;
+        reports::ft260_clear_interrupt(&self.device);
+        Ok(())
+    }
+
+    /// Wait for an interrupt until `timeout` elapses
+    pub fn wait(&self, timeout: Duration) -> Option<Event> {
+        reports::ft260_wait_interrupt(&self.device, Instant::now() + timeout)
+            .map(|st| Event::from_hid(&st))
+    }
+
+    /// Take an interrupt triggered already without waiting
+    pub fn try_wait(&self) -> Option<Event> {
+        reports::ft260_pop_interrupt(&self.device).map(|st| Event::from_hid(&st))
+    }
+
+    /// Call `callback` with each interrupt on a thread until the returned `Listener` is dropped
+    pub fn on_event<F>(&self, mut callback: F) -> Listener
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        Listener::spawn(self.device.clone(), move |event| {
+            callback(event);
+            true
+        })
+    }
+
+    /// Send each interrupt to the returned channel until the `Listener` or the receiver is dropped
+    pub fn channel(&self) -> (Listener, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel();
+        let listener = Listener::spawn(self.device.clone(), move |event| tx.send(event).is_ok());
+        (listener, rx)
+    }
+}
+
+impl<S: State> Drop for Interrupt<S> {
+    fn drop(&mut self) {
+        if S::INITIALIZED {
+            // switch INTRIN back to GPIO3
+            if let Err(e) =
+                reports::ft260_set_wakeup_interrupt(&self.device, WakeupIntEnableMode::Disabled)
+            {
+                tracing::warn!(error = %e, "failed to disable INTRIN");
+            }
+        }
+    }
+}
+
+/// Async API on tokio runtime
+#[cfg(feature = "tokio")]
+impl Interrupt<Initialized> {
+    /// Wait for an interrupt until `timeout` elapses asynchronously
+    pub async fn wait_async(&self, timeout: Duration) -> Option<Event> {
+        reports::ft260_wait_interrupt_async(&self.device, Instant::now() + timeout)
+            .await
+            .map(|st| Event::from_hid(&st))
+    }
+}
+
+/// Thread receiving interrupts for `Interrupt::on_event` or `Interrupt::channel`,
+/// which stops when dropped
+#[derive(Debug)]
+pub struct Listener {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Listener {
+    /// Spawn a thread passing interrupts to `handle` while it returns `true`
+    fn spawn(device: Device, mut handle: impl FnMut(Event) -> bool + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let deadline = Instant::now() + LISTEN_INTERVAL;
+                    if let Some(st) = reports::ft260_wait_interrupt(&device, deadline) {
+                        if !handle(Event::from_hid(&st)) {
+                            break;
+                        }
+                    }
+                }
+                tracing::debug!("stopped listening interrupts");
+            }
+        });
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// If the thread is still receiving interrupts
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            // not to join itself when dropped in the callback
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
@@ -1,57 +1,16 @@
+use std::marker::PhantomData;
 use std::time::Duration;
 
-use crate::device;
+use crate::chip::{ClockPolicy, Rate};
+use tracing::field::Empty;
+
+use crate::codec;
+pub use crate::codec::uart::{Breaking, DataBits, Mode, Parity, StopBit};
+use crate::device::{Device, PinClaim};
 use crate::hid::consts::*;
 use crate::hid::reports;
-use crate::io::gpio;
-use crate::{device::Device, Ft260Result};
-
-macro_rules! hid_const_compatible {
-  (
-    $tgt:ident,
-    $(#[$meta:meta])*
-    $vis:vis enum $name:ident {
-      $($(#[$vmeta:meta])*
-      $vname:ident $(= $val:expr)?,)*
-    }
-  ) => {
-    $(#[$meta])*
-    $vis enum $name {
-      $($(#[$vmeta])* $vname $(= $val)?,)*
-    }
-
-    impl $name {
-      fn to_hid_const(&self) -> $tgt {
-        match self {
-          $($name::$vname => $tgt::$vname,)*
-        }
-      }
-      fn from_hid_const(v:&$tgt) -> Self {
-        match v {
-          $($tgt::$vname => $name::$vname,)*
-        }
-      }
-    }
-}
-}
-
-hid_const_compatible! {
-  UartEnableMode,
-  /// UART Flow Control Mode
-  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-  pub enum Mode {
-    /// > "OFF, and switch UART pins to GPIO"
-    Off,
-    /// > "RTS_CTS mode (GPIOB =>RTSN, GPIOE =>CTSN)"
-    RtsCts,
-    /// > "DTR_DSR mode (GPIOF =>DTRN, GPIOH => DSRN)"
-    DtrDsr,
-    /// > "XON_XOFF (software flow control)"
-    XonXoff,
-    /// > "No flow control mode"
-    NoFlowControl,
-  }
-}
+use crate::io::{self, gpio, Initialized, State, Uninitialized};
+use crate::{Ft260Error, Ft260Result};
 
 hid_const_compatible! {
   UartDcdRiEnableMode,
@@ -63,60 +22,6 @@ hid_const_compatible! {
   }
 }
 
-hid_const_compatible! {
-  UartParity,
-  /// UART Parity mode
-  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-  pub enum Parity {
-    /// No parity
-    None,
-    /// Odd parity
-    Odd,
-    /// Even parity
-    Even,
-    /// Parity bit is always high
-    High,
-    /// Parity bit is always low
-    Low,
-  }
-}
-
-hid_const_compatible! {
-  UartStopBit,
-  /// Stop bit
-  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-  pub enum StopBit {
-      /// one stop bit
-      One,
-      /// two stop bits
-      Two,
-  }
-}
-
-hid_const_compatible! {
-  UartBreaking,
-  /// > "When active the TXD line goes into ‘spacing’ state which causes a break in the receiving UART."
-  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-  pub enum Breaking {
-      /// No break
-      NoBreak,
-      /// Break
-      Break,
-  }
-}
-
-hid_const_compatible! {
-  UartDataBits,
-  /// The number of UART data bits
-  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-  pub enum DataBits {
-      /// 7 data bits
-      Seven,
-      /// 8 data bits
-      Eight,
-  }
-}
-
 /// Parameters set to configure UART function
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Config {
@@ -150,129 +55,226 @@ impl Config {
             breaking: Breaking::NoBreak,
         }
     }
-    fn from_hid(cfg: &reports::uart::Config) -> Self {
+    fn from_hid(cfg: &codec::uart::UartConfig) -> Self {
         Self {
-            mode: Mode::from_hid_const(&cfg.mode),
+            mode: cfg.mode,
             baud: cfg.baud_rate,
-            data_bits: DataBits::from_hid_const(&cfg.data_bits),
-            stop_bit: StopBit::from_hid_const(&cfg.stop_bit),
-            parity: Parity::from_hid_const(&cfg.parity),
-            breaking: Breaking::from_hid_const(&cfg.breaking),
+            data_bits: cfg.data_bits,
+            stop_bit: cfg.stop_bit,
+            parity: cfg.parity,
+            breaking: cfg.breaking,
         }
     }
 }
 
-/// Interface type to use UART function of the FT2260 device.
-#[derive(Debug)]
-pub struct Uart<'a> {
-    device: &'a Device,
-    inited: bool,
+/// Fail if `len` exceeds the buffer
+fn check_len(buf_len: usize, len: usize) -> Ft260Result<()> {
+    if len > buf_len {
+        return Err(Ft260Error::InvalidArgument {
+            message: format!("length {} exceeds buffer of {} byte(s)", len, buf_len),
+        });
+    }
+    Ok(())
 }
 
-macro_rules! dbg_write {
-    ($msg:expr) => {
-        if cfg!(debug_assertions) {
-            println!("[DEBUG] {}:{} '{}'", file!(), line!(), $msg);
-        }
-    };
+/// Interface type to use UART function of the FT2260 device.
+/// It's `Uninitialized` when created, and `init` turns it into `Uart<Initialized>`,
+/// which is the only state with methods to configure and transfer data.
+#[derive(Debug)]
+pub struct Uart<S: State = Uninitialized> {
+    device: Device,
+    /// ownership of UART pins while initialized
+    _pins: Option<PinClaim>,
+    state: PhantomData<S>,
 }
 
-impl<'a> Uart<'a> {
-    pub(crate) fn new(device: &'a Device) -> Self {
+impl Uart<Uninitialized> {
+    pub(crate) fn new(device: Device) -> Self {
         Self {
             device,
-            inited: false,
+            _pins: None,
+            state: PhantomData,
         }
     }
 
-    /// Initialize UART function
-    pub fn init(&mut self) -> Ft260Result<()> {
-        let device = self.device;
-        let gpio = device.gpio();
-        if let Err(e) = gpio.disable_pin(gpio::Group::Gpio_B_C_D_E_F_H) {
-            dbg!(&e);
-            dbg_write!("Failed to disable Gpio B,C,D,E,F,H");
+    /// Initialize UART function.
+    /// The UART pins are owned by the initialized instance until it's dropped,
+    /// and it fails with `Ft260Error::PinConflict` if GPIO owns them.
+    pub fn init(self) -> Ft260Result<Uart<Initialized>> {
+        let device = &self.device;
+        let pins = device.claim_pins(gpio::Group::Gpio_B_C_D_E_F_H, gpio::Owner::Uart)?;
+        if let Err(e) = gpio::disable(device, gpio::Group::Gpio_B_C_D_E_F_H) {
+            tracing::debug!(error = %e, "failed to disable GPIO B, C, D, E, F and H");
             return Err(e);
         }
         if let Err(e) = reports::uart::init(device) {
-            dbg!(&e);
-            dbg_write!("Failed to initialize UART function");
+            tracing::debug!(error = %e, "failed to initialize UART");
             return Err(e);
         }
-        self.inited = true;
-        Ok(())
+        tracing::debug!("initialized UART");
+        Ok(Uart {
+            device: self.device.clone(),
+            _pins: Some(pins),
+            state: PhantomData,
+        })
+    }
+
+    /// Initialize UART function and configure it with parameters set
+    pub fn init_with_config(self, cfg: &Config) -> Ft260Result<Uart<Initialized>> {
+        let uart = self.init()?;
+        uart.set_config(cfg)?;
+        Ok(uart)
+    }
+}
+
+impl<S: State> Uart<S> {
+    /// Get current UART configuration parameters
+    pub fn get_config(&self) -> Ft260Result<Config> {
+        let cfg = reports::uart::get_config(&self.device)
+            .inspect_err(|e| tracing::debug!(error = %e, "failed to get UART config"))?;
+        Ok(Config::from_hid(&cfg))
     }
 
-    /// Configure UART function with parameters set
+    /// Get data amount in RX data FIFO
+    pub fn size_to_read(&self) -> usize {
+        reports::uart::get_queue_status(&self.device)
+    }
+}
+
+impl Uart<Initialized> {
+    /// Configure UART function with parameters set.
+    /// The current system clock is kept, and the baud rate is passed to the chip as it is.
     pub fn set_config(&self, cfg: &Config) -> Ft260Result<()> {
-        let device = self.device;
+        self.set_config_with_clock(cfg, ClockPolicy::Keep)
+            .map(|_| ())
+    }
+
+    /// Configure UART function with parameters set, choosing the system clock according to `policy`.
+    /// Returns the clock selected with the baud rate passed to the chip.
+    /// Fails if no clock supports the baud rate for `ClockPolicy::Lowest`.
+    pub fn set_config_with_clock(&self, cfg: &Config, policy: ClockPolicy) -> Ft260Result<Rate> {
+        let device = &self.device;
+
+        let rate = device
+            .select_clock(policy, cfg.baud, |clock| clock.supports_uart_baud(cfg.baud))?
+            .ok_or_else(|| Ft260Error::UartError {
+                message: format!(
+                    "Baud rate {} is not supported with clock policy {:?}",
+                    cfg.baud, policy
+                ),
+            })?;
 
         if let Err(e) = reports::uart::set_flow_control(device, cfg.mode.to_hid_const()) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART flow control");
             return Err(e);
         }
         if let Err(e) = reports::uart::set_baud_rate(device, cfg.baud) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART baud rate");
             return Err(e);
         }
         if let Err(e) = reports::uart::set_data_bits(device, cfg.data_bits.to_hid_const()) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART data bits");
             return Err(e);
         }
         if let Err(e) = reports::uart::set_stop_bit(device, cfg.stop_bit.to_hid_const()) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART stop bit");
             return Err(e);
         }
         if let Err(e) = reports::uart::set_parity(device, cfg.parity.to_hid_const()) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART parity");
             return Err(e);
         }
         if let Err(e) = reports::uart::set_breaking(device, cfg.breaking.to_hid_const()) {
-            dbg!(&e);
+            tracing::debug!(error = %e, "failed to set UART breaking");
             return Err(e);
         }
 
-        Ok(())
-    }
-
-    /// Get current UART configuration parameters
-    pub fn get_config(&self) -> Ft260Result<Config> {
-        let device = self.device;
-        let cfg = reports::uart::get_config(device);
-        if cfg.is_err() {
-            dbg_write!("Failed to get UART config");
-            return Err(cfg.unwrap_err());
-        }
-        let cfg = cfg.unwrap();
-        Ok(Config::from_hid(&cfg))
-    }
-
-    /// Get data amount in RX data FIFO
-    pub fn size_to_read(&self) -> usize {
-        reports::uart::get_queue_status(self.device)
+        tracing::debug!(config = ?cfg, clock = ?rate.clock, "configured UART");
+        Ok(rate)
     }
 
     /// Read RX data from FIFO
     pub fn read(&self, buf: &mut [u8], len: usize, duration_wait: Duration) -> Ft260Result<usize> {
-        reports::uart::read(self.device, buf, len, duration_wait)
+        check_len(buf.len(), len)?;
+        let _lock = self.device.lock_uart_read();
+        let span = tracing::debug_span!(
+            "uart_read",
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        io::traced(span, || {
+            reports::uart::read(&self.device, buf, len, duration_wait)
+        })
     }
 
     /// Write TX data
     pub fn write(&self, buf: &[u8], len: usize) -> Ft260Result<usize> {
-        reports::uart::write(self.device, buf, len)
+        check_len(buf.len(), len)?;
+        let _lock = self.device.lock_uart_write();
+        let span = tracing::debug_span!(
+            "uart_write",
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        io::traced(span, || reports::uart::write(&self.device, buf, len))
+    }
+}
+
+/// Async API on tokio runtime.
+/// Waiting for RX data doesn't block the executor thread,
+/// and other HID transfers run on the blocking thread pool.
+#[cfg(feature = "tokio")]
+impl Uart<Initialized> {
+    /// Read RX data asynchronously
+    pub async fn read_async(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        duration_wait: Duration,
+    ) -> Ft260Result<usize> {
+        check_len(buf.len(), len)?;
+        let _lock = self.device.lock_uart_read_async().await;
+        let span = tracing::debug_span!(
+            "uart_read",
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        let read = reports::uart::read_async(&self.device, buf, len, duration_wait);
+        io::traced_async(span, read).await
     }
 
-    /// Reset UART function  
-    /// > "The request will reset the FT260 UART controller."
-    fn reset(&self) -> Ft260Result<()> {
-        reports::uart::reset(self.device)
+    /// Write TX data asynchronously
+    pub async fn write_async(&self, buf: &[u8], len: usize) -> Ft260Result<usize> {
+        check_len(buf.len(), len)?;
+        let _lock = self.device.lock_uart_write_async().await;
+        let span = tracing::debug_span!(
+            "uart_write",
+            len,
+            report_id = Empty,
+            bytes = Empty,
+            elapsed_us = Empty
+        );
+        let data = buf[..len].to_vec();
+        let write = self
+            .device
+            .run_blocking(move |device| reports::uart::write(device, &data, data.len()));
+        io::traced_async(span, write).await
     }
 }
 
-impl<'a> Drop for Uart<'a> {
+impl<S: State> Drop for Uart<S> {
     fn drop(&mut self) {
-        if self.inited {
-            self.reset().expect("UART should be reset successfully")
+        // > "The request will reset the FT260 UART controller."
+        if S::INITIALIZED {
+            if let Err(e) = reports::uart::reset(&self.device) {
+                tracing::warn!(error = %e, "failed to reset UART controller");
+            }
         }
     }
 }
@@ -1,12 +1,52 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+mod macros;
+
+/// module to record reports transferred by devices into file
+#[cfg(feature = "std")]
+pub mod capture;
+/// module to handle FT260 chip with all of its HID interfaces
+#[cfg(feature = "std")]
+pub mod chip;
+/// allocation-free encoding and decoding of FT260 reports, usable without `std`
+pub mod codec;
 /// module to control FT260 HID device
+#[cfg(feature = "std")]
 pub mod device;
+#[cfg(feature = "std")]
 mod error;
+#[cfg_attr(not(feature = "std"), allow(dead_code, unused_imports))]
 mod hid;
+/// module to watch FT260 HID interfaces plugged in and unplugged
+#[cfg(feature = "std")]
+pub mod hotplug;
 /// interface modules to use GPIO, I2C and UART features
+#[cfg(feature = "std")]
 pub mod io;
+/// module to query and configure USB suspend behaviour of FT260
+#[cfg(feature = "std")]
+pub mod power;
+/// raw HID reports for chip features not wrapped by this crate, to use with care
+#[cfg(feature = "std")]
+pub mod raw;
+/// software model of FT260 chip to use without hardware
+#[cfg(feature = "std")]
+pub mod sim;
+/// abstraction of HID interface under `device::Device`
+#[cfg(feature = "std")]
+pub mod transport;
 
 /// common Error type in this crate
+#[cfg(feature = "std")]
 pub use error::Ft260Error;
+/// reasons of `Ft260Error::I2cBusError`
+#[cfg(feature = "std")]
+pub use error::I2cErrorKind;
+/// reasons of `Ft260Error::OpenError`
+#[cfg(feature = "std")]
+pub use error::OpenErrorKind;
 
 /// common Result type in this crate
+#[cfg(feature = "std")]
 pub type Ft260Result<T> = Result<T, Ft260Error>;
@@ -0,0 +1,55 @@
+// see "https://stackoverflow.com/questions/28028854/how-do-i-match-enum-values-with-an-integer"
+/// Define an enum with `TryFrom<u8>` taking the discriminants of its variants
+macro_rules! back_to_enum {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident {
+        $($(#[$vmeta:meta])* $vname:ident $(= $val:expr)?,)*
+    }) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($(#[$vmeta])* $vname $(= $val)?,)*
+        }
+
+        impl core::convert::TryFrom<u8> for $name {
+            type Error = $crate::codec::Error;
+            fn try_from(v: u8) -> Result<Self, Self::Error> {
+                match v {
+                    $(x if x == $name::$vname as u8 => Ok($name::$vname),)*
+                    _ => Err($crate::codec::Error::InvalidValue { value: v }),
+                }
+            }
+        }
+    }
+}
+
+/// Define a public enum mirroring a crate-private enum in `hid::consts` which has the same variant names,
+/// and conversions between them
+macro_rules! hid_const_compatible {
+  (
+    $tgt:ident,
+    $(#[$meta:meta])*
+    $vis:vis enum $name:ident {
+      $($(#[$vmeta:meta])*
+      $vname:ident $(= $val:expr)?,)*
+    }
+  ) => {
+    $(#[$meta])*
+    $vis enum $name {
+      $($(#[$vmeta])* $vname $(= $val)?,)*
+    }
+
+    impl $name {
+      #[allow(dead_code)]
+      pub(crate) fn to_hid_const(self) -> $tgt {
+        match self {
+          $($name::$vname => $tgt::$vname,)*
+        }
+      }
+      #[allow(dead_code)]
+      pub(crate) fn from_hid_const(v:&$tgt) -> Self {
+        match v {
+          $($tgt::$vname => $name::$vname,)*
+        }
+      }
+    }
+}
+}
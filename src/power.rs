@@ -0,0 +1,251 @@
+use crate::chip::SuspendOutPolarity;
+use crate::device::Device;
+use crate::hid::consts::{GpioPinNum, UartRiWakeupConfig, WakeupIntEnableMode};
+use crate::hid::reports;
+use crate::io::gpio::{self, Gpio, Group, Owner, Pin, Val};
+use crate::{Ft260Error, Ft260Result};
+
+/// USB power state of the chip, taken from System Status report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerStatus {
+    /// The USB host has suspended the chip
+    pub suspended: bool,
+    /// The chip is USB enumerated and PWREN is asserted
+    pub pwren_ready: bool,
+    /// Power saving mode is enabled
+    pub power_saving_enabled: bool,
+    /// Polarity of SUSPOUT output
+    pub suspend_out_polarity: SuspendOutPolarity,
+}
+
+hid_const_compatible! {
+  UartRiWakeupConfig,
+  /// Edge of UART RI (GPIO5) to wake up the USB host
+  #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+  pub enum RiWakeup {
+    /// Rising edge
+    RisingEdge,
+    /// Falling edge, as a ring pulls RI low (default)
+    FallingEdge,
+  }
+}
+
+/// Sources of USB remote wakeup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WakeupConfig {
+    /// GPIO3 works as WAKEUP input, as read from the chip
+    pub wakeup_pin: bool,
+    /// Edge of UART RI to wake up the host, `None` if it's disabled.
+    /// The chip doesn't report it, so it's the one configured by `Power` of any interface of the chip.
+    pub ri: Option<RiWakeup>,
+}
+
+/// Levels of GPIO pins driven while the USB host suspends the chip.
+/// Pins not given keep their state.
+///
+/// ```
+/// use ft260hid::io::gpio::{Pin, Val};
+/// use ft260hid::power::SuspendLevels;
+///
+/// // turn off the regulator and hold the reset of peripherals
+/// let levels = SuspendLevels::new()
+///     .pin(Pin::GpioA, Val::Low)
+///     .pin(Pin::Gpio4, Val::Low);
+/// assert_eq!(levels.level(Pin::GpioA), Some(Val::Low));
+/// assert_eq!(levels.level(Pin::GpioB), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuspendLevels {
+    /// pins to drive
+    mask: GpioPinNum,
+    /// levels of pins to drive, high if set
+    high: GpioPinNum,
+}
+
+impl SuspendLevels {
+    /// No pin driven on suspend
+    pub fn new() -> Self {
+        Self {
+            mask: GpioPinNum::empty(),
+            high: GpioPinNum::empty(),
+        }
+    }
+
+    /// Drive `pin` at `val` on suspend
+    pub fn pin(mut self, pin: Pin, val: Val) -> Self {
+        let bit = Gpio::pin_to_num(pin);
+        self.mask.insert(bit);
+        self.high.set(bit, val == Val::High);
+        self
+    }
+
+    /// Level driven at `pin` on suspend, `None` if it's not driven
+    pub fn level(&self, pin: Pin) -> Option<Val> {
+        let bit = Gpio::pin_to_num(pin);
+        if !self.mask.contains(bit) {
+            None
+        } else if self.high.contains(bit) {
+            Some(Val::High)
+        } else {
+            Some(Val::Low)
+        }
+    }
+
+    /// Parameters of requests for GPIO0-5 and GPIOA-H,
+    /// each of which has the mask in the low byte and the levels in the high byte
+    fn params(&self) -> (u16, u16) {
+        let (mask, high) = (self.mask.bits(), self.high.bits());
+        let gpio_0 = (mask & 0x3F) | ((high & 0x3F) << 8);
+        let gpio_a = ((mask >> 6) & 0xFF) | (((high >> 6) & 0xFF) << 8);
+        (gpio_0, gpio_a)
+    }
+}
+
+impl Default for SuspendLevels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interface type to query and configure USB suspend behaviour of the chip,
+/// e.g. to drop peripherals of a bus-powered board to a safe state while the host suspends it.
+///
+/// USB remote wakeup can be enabled on WAKEUP (GPIO3) and on UART RI (GPIO5).
+/// Their pin groups are owned by `Owner::Wakeup` from enabling it until it's disabled,
+/// regardless of the `Power` instance doing so, and GPIO can't take them meanwhile.
+///
+/// Power saving mode is taken from the configuration in EEPROM or OTP of the chip,
+/// which can't be changed by a request, so that `PowerStatus` only reports it.
+///
+/// ```no_run
+/// use ft260hid::chip::SuspendOutPolarity;
+/// use ft260hid::device;
+/// use ft260hid::io::gpio::{Pin, Val};
+/// use ft260hid::power::SuspendLevels;
+///
+/// let dev = device::open(0).unwrap();
+/// let power = dev.power();
+/// power.set_suspend_out_polarity(SuspendOutPolarity::Low).unwrap();
+/// power
+///     .set_suspend_levels(&SuspendLevels::new().pin(Pin::GpioA, Val::Low))
+///     .unwrap();
+/// assert!(power.status().unwrap().pwren_ready);
+/// ```
+#[derive(Debug)]
+pub struct Power {
+    device: Device,
+}
+
+impl Power {
+    pub(crate) fn new(device: Device) -> Self {
+        Self { device }
+    }
+
+    /// Read USB power state of the chip
+    pub fn status(&self) -> Ft260Result<PowerStatus> {
+        let st = self.device.system_status()?;
+        Ok(PowerStatus {
+            suspended: st.suspended,
+            pwren_ready: st.pwren_ready,
+            power_saving_enabled: st.power_saving_enabled,
+            suspend_out_polarity: st.suspend_out_polarity,
+        })
+    }
+
+    /// If the USB host has suspended the chip
+    pub fn is_suspended(&self) -> Ft260Result<bool> {
+        Ok(self.status()?.suspended)
+    }
+
+    /// If the chip is USB enumerated and PWREN is asserted
+    pub fn is_pwren_ready(&self) -> Ft260Result<bool> {
+        Ok(self.status()?.pwren_ready)
+    }
+
+    /// Set polarity of SUSPOUT output, which is on GPIO2 while its function is `Gpio2Function::SuspOut`
+    pub fn set_suspend_out_polarity(&self, polarity: SuspendOutPolarity) -> Ft260Result<()> {
+        reports::gpio::set_suspend_out_polarity(&self.device, polarity.to_hid_const())
+    }
+
+    /// Set levels of GPIO pins driven while USB is suspended, replacing the previous ones.
+    /// The chip doesn't report them back.
+    pub fn set_suspend_levels(&self, levels: &SuspendLevels) -> Ft260Result<()> {
+        let (gpio_0, gpio_a) = levels.params();
+        reports::gpio::set_suspend_mode(&self.device, gpio_0, gpio_a)
+    }
+
+    /// Enable USB remote wakeup by WAKEUP input on GPIO3, which is kept until `disable_wakeup_pin`.
+    /// Fails with `Ft260Error::PinConflict` if GPIO or interrupt function owns the pin.
+    pub fn enable_wakeup_pin(&self) -> Ft260Result<()> {
+        self.hold(Group::Gpio_3, || {
+            reports::ft260_set_wakeup_interrupt(&self.device, WakeupIntEnableMode::Enabled)
+        })
+    }
+
+    /// Disable wakeup by GPIO3, which works as GPIO then
+    pub fn disable_wakeup_pin(&self) -> Ft260Result<()> {
+        self.release(Group::Gpio_3, || {
+            reports::ft260_set_wakeup_interrupt(&self.device, WakeupIntEnableMode::Disabled)
+        })
+    }
+
+    /// Enable USB remote wakeup by `edge` of UART RI on GPIO5, switching GPIO4 and 5 to DCD and RI.
+    /// It's kept until `disable_ri_wakeup`.
+    /// Fails with `Ft260Error::PinConflict` if GPIO owns the pins.
+    pub fn enable_ri_wakeup(&self, edge: RiWakeup) -> Ft260Result<()> {
+        self.hold(Group::Gpio_4_5, || {
+            gpio::disable(&self.device, Group::Gpio_4_5)?;
+            reports::uart::set_ri_wakeup_config(&self.device, edge.to_hid_const())?;
+            reports::uart::enable_ri_wakeup(&self.device, WakeupIntEnableMode::Enabled)
+        })?;
+        self.device.set_ri_wakeup(Some(edge));
+        Ok(())
+    }
+
+    /// Disable wakeup by UART RI, leaving GPIO4 and 5 as DCD and RI
+    pub fn disable_ri_wakeup(&self) -> Ft260Result<()> {
+        self.release(Group::Gpio_4_5, || {
+            reports::uart::enable_ri_wakeup(&self.device, WakeupIntEnableMode::Disabled)
+        })?;
+        self.device.set_ri_wakeup(None);
+        Ok(())
+    }
+
+    /// Sources of USB remote wakeup currently enabled
+    pub fn wakeup_config(&self) -> Ft260Result<WakeupConfig> {
+        let st = self.device.system_status()?;
+        Ok(WakeupConfig {
+            wakeup_pin: st.wakeup_interrupt_enabled,
+            ri: self.device.ri_wakeup(),
+        })
+    }
+
+    /// Hold `group` for wakeup, and release it again if `configure` fails
+    /// unless wakeup held it before
+    fn hold(&self, group: Group, configure: impl FnOnce() -> Ft260Result<()>) -> Ft260Result<()> {
+        let held = self.device.hold_pins(group, Owner::Wakeup)?;
+        configure().inspect_err(|_| {
+            if held {
+                self.device.release_pins(group, Owner::Wakeup);
+            }
+        })
+    }
+
+    /// Release `group` held for wakeup after `configure` succeeds.
+    /// Fails with `Ft260Error::PinConflict` if the group is owned by others.
+    fn release(
+        &self,
+        group: Group,
+        configure: impl FnOnce() -> Ft260Result<()>,
+    ) -> Ft260Result<()> {
+        match self.device.pin_owner(group) {
+            Some(owner) if owner != Owner::Wakeup => {
+                return Err(Ft260Error::PinConflict { group, owner })
+            }
+            _ => {}
+        }
+        configure()?;
+        self.device.release_pins(group, Owner::Wakeup);
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+
+use crate::codec::gpio::GpioReport;
+use crate::codec::i2c::{self, I2cStatus};
+use crate::codec::system::{ChipCode, SystemStatus};
+use crate::codec::uart::{self, UartConfig};
+use crate::device::{Device, ReportClass};
+use crate::{Ft260Error, Ft260Result};
+
+/// HID report IDs of FT260
+pub use crate::codec::ReportId;
+/// Requests of System Setting feature report (0xA1)
+pub use crate::codec::Request;
+/// Maximum length of HID report including ID
+pub use crate::codec::REPORT_LENGTH_MAX;
+
+/// Interface type to transfer HID reports as they are, for chip features not wrapped by this crate.
+///
+/// **Use with care.** Reports bypass the state kept by `Gpio`, `I2c` and `Uart`,
+/// e.g. pin ownership and initialization, so they may break operations of those instances.
+/// Only lengths and directions of reports are checked.
+#[derive(Debug)]
+pub struct Raw {
+    device: Device,
+}
+
+/// Input report popped from FIFO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Report ID
+    pub id: ReportId,
+    /// Bytes following the report ID
+    pub payload: Vec<u8>,
+}
+
+/// Length limit of a report including ID, which is exact or the maximum
+#[derive(Debug, Clone, Copy)]
+enum Length {
+    Exact(usize),
+    Max(usize),
+}
+
+impl ReportId {
+    /// Length of feature report returned by the chip, including ID
+    fn get_feature_length(self) -> Option<usize> {
+        match self {
+            ReportId::FeatChipCode => Some(ChipCode::LENGTH),
+            ReportId::FeatSystemSetting => Some(SystemStatus::LENGTH),
+            ReportId::FeatGpio => Some(GpioReport::LENGTH),
+            ReportId::FeatI2cStatus => Some(I2cStatus::LENGTH),
+            ReportId::FeatUartStatus => Some(UartConfig::LENGTH),
+            ReportId::FeatUartRiAndDcdStatus => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Length of feature report sent to the chip
+    fn set_feature_length(self) -> Option<Length> {
+        match self {
+            ReportId::FeatSystemSetting => Some(Length::Max(REPORT_LENGTH_MAX)),
+            ReportId::FeatGpio => Some(Length::Exact(GpioReport::LENGTH)),
+            _ => None,
+        }
+    }
+
+    /// Length of output report
+    fn output_length(self) -> Option<Length> {
+        if self == ReportId::OutI2cReadRequest {
+            return Some(Length::Exact(5));
+        }
+        // address, condition and length precede I2C data, and length precedes UART data
+        i2c::capacity(self)
+            .map(|n| Length::Max(4 + n))
+            .or_else(|| uart::capacity(self).map(|n| Length::Max(2 + n)))
+    }
+
+    /// Class of input report
+    fn input_class(self) -> Option<ReportClass> {
+        match self as u8 {
+            0xB1 => Some(ReportClass::Interrupt),
+            0xD0..=0xDE => Some(ReportClass::I2c),
+            0xF0..=0xFE => Some(ReportClass::Uart),
+            _ => None,
+        }
+    }
+}
+
+/// Build a report from ID and payload, checking its length
+fn build(id: ReportId, payload: &[u8], length: Option<Length>, kind: &str) -> Ft260Result<Vec<u8>> {
+    let len = payload.len() + 1;
+    let ok = match length {
+        None => {
+            return Err(Ft260Error::InvalidArgument {
+                message: format!("{:?} is not {} report", id, kind),
+            })
+        }
+        Some(Length::Exact(n)) => len == n,
+        Some(Length::Max(n)) => len <= n,
+    };
+    if !ok {
+        return Err(Ft260Error::InvalidArgument {
+            message: format!(
+                "{} report {:?} can't be {} byte(s) long ({:?})",
+                kind, id, len, length
+            ),
+        });
+    }
+    let mut data = Vec::with_capacity(len);
+    data.push(id as u8);
+    data.extend_from_slice(payload);
+    Ok(data)
+}
+
+impl Raw {
+    pub(crate) fn new(device: Device) -> Self {
+        Self { device }
+    }
+
+    /// Send feature report with `payload` following the ID.
+    /// Fails with `Ft260Error::InvalidArgument` if the chip doesn't accept the report or the length.
+    pub fn set_feature(&self, id: ReportId, payload: &[u8]) -> Ft260Result<()> {
+        let data = build(id, payload, id.set_feature_length(), "feature")?;
+        self.device.set_feature(&data)
+    }
+
+    /// Get feature report, returning the bytes following the ID
+    pub fn get_feature(&self, id: ReportId) -> Ft260Result<Vec<u8>> {
+        let len = id
+            .get_feature_length()
+            .ok_or_else(|| Ft260Error::InvalidArgument {
+                message: format!("{:?} is not feature report to get", id),
+            })?;
+        let mut buf = [0u8; REPORT_LENGTH_MAX];
+        buf[0] = id as u8;
+        let sz = self.device.get_feature(&mut buf)?;
+        if buf[0] != id as u8 || sz < len {
+            return Err(Ft260Error::UnexpectedReport {
+                report_id: buf[0],
+                message: format!("{} byte(s) returned for {:?}", sz, id),
+            });
+        }
+        Ok(buf[1..len].to_vec())
+    }
+
+    /// Send System Setting request (0xA1) with its parameters,
+    /// e.g. `Request::SetDriveStrength0` with a byte of strength
+    pub fn set_request(&self, request: Request, params: &[u8]) -> Ft260Result<()> {
+        let mut payload = Vec::with_capacity(params.len() + 1);
+        payload.push(request as u8);
+        payload.extend_from_slice(params);
+        self.set_feature(ReportId::FeatSystemSetting, &payload)
+    }
+
+    /// Send output report with `payload` following the ID.
+    /// Fails with `Ft260Error::InvalidArgument` if it's not output report or the payload exceeds it.
+    pub fn write_output(&self, id: ReportId, payload: &[u8]) -> Ft260Result<()> {
+        let data = build(id, payload, id.output_length(), "output")?;
+        self.device.write_output(&data)
+    }
+
+    /// Pop input reports of a class pending in FIFO.
+    /// It waits for the I2C or UART transfer in progress, not to take reports it's reading.
+    pub fn take_input(&self, class: ReportClass) -> Vec<Report> {
+        self.wait_input(class, Duration::ZERO)
+    }
+
+    /// Pop input reports of a class in FIFO, waiting for the first one until `timeout` elapses
+    pub fn wait_input(&self, class: ReportClass, timeout: Duration) -> Vec<Report> {
+        let _lock = match class {
+            ReportClass::I2c => Some(self.device.lock_i2c()),
+            ReportClass::Uart => Some(self.device.lock_uart_read()),
+            ReportClass::Interrupt => None,
+        };
+        let deadline = Instant::now() + timeout;
+        let mut reports = Vec::new();
+        while reports.is_empty() {
+            match self.device.wait_input_report(class.id(), deadline) {
+                Some(data) => reports.extend(Self::to_report(data)),
+                None => return reports,
+            }
+        }
+        while let Some(data) = self.device.fifo().pop_report(class.id()) {
+            reports.extend(Self::to_report(data));
+        }
+        reports
+    }
+
+    /// Typed report from bytes in FIFO, which may be padded beyond the maximum length
+    fn to_report(data: Vec<u8>) -> Option<Report> {
+        let id = ReportId::try_from(*data.first()?).ok()?;
+        id.input_class()?;
+        let len = data.len().min(REPORT_LENGTH_MAX);
+        Some(Report {
+            id,
+            payload: data[1..len].to_vec(),
+        })
+    }
+}
@@ -0,0 +1,841 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::chip::{ChipMode, FirmwareVersion};
+use crate::io::gpio::Pin;
+use crate::transport::Transport;
+use crate::{Ft260Error, Ft260Result};
+
+/// submodule of I2C target models
+pub mod i2c;
+
+pub use i2c::{Eeprom, I2cTarget, RegisterFile};
+
+const REPORT_SIZE: usize = 64;
+const PAYLOAD_SIZE_MAX: usize = 0x3C;
+
+const I2C_STATUS_ERROR: u8 = 0x02;
+const I2C_STATUS_ADDRESS_NACK: u8 = 0x04;
+const I2C_STATUS_DATA_NACK: u8 = 0x08;
+const I2C_STATUS_CONTROLLER_IDLE: u8 = 0x20;
+const I2C_STATUS_BUS_BUSY: u8 = 0x40;
+
+const I2C_FLAG_START: u8 = 0x02;
+const I2C_FLAG_STOP: u8 = 0x04;
+
+/// Values in System Status report (0xA1)
+#[derive(Debug, Clone, Copy)]
+struct System {
+    chip_mode: u8,
+    clock: u8,
+    suspend_status: u8,
+    pwren_status: u8,
+    i2c_enable: u8,
+    uart_mode: u8,
+    hid_over_i2c: u8,
+    gpio2_function: u8,
+    gpio_a_function: u8,
+    gpio_g_function: u8,
+    suspend_out_pol: u8,
+    wakeup_int: u8,
+    interrupt_trigger: u8,
+    interrupt_duration: u8,
+    power_saving: u8,
+}
+
+impl System {
+    /// Values after power-on with chip mode strapped by `DCNF0` and `DCNF1`
+    fn power_on(chip_mode: u8) -> Self {
+        Self {
+            chip_mode,
+            clock: 2, // 48MHz
+            suspend_status: 0,
+            pwren_status: 1,
+            i2c_enable: 1,
+            uart_mode: 4,
+            hid_over_i2c: 0,
+            gpio2_function: 1,
+            gpio_a_function: 3,
+            gpio_g_function: 6,
+            suspend_out_pol: 0,
+            wakeup_int: 1,
+            interrupt_trigger: 0,
+            interrupt_duration: 0,
+            power_saving: 0,
+        }
+    }
+
+    fn report(&self) -> [u8; 26] {
+        let mut rep = [0u8; 26];
+        rep[0] = 0xA1;
+        rep[1] = self.chip_mode;
+        rep[2] = self.clock;
+        rep[3] = self.suspend_status;
+        rep[4] = self.pwren_status;
+        rep[5] = self.i2c_enable;
+        rep[6] = self.uart_mode;
+        rep[7] = self.hid_over_i2c;
+        rep[8] = self.gpio2_function;
+        rep[9] = self.gpio_a_function;
+        rep[10] = self.gpio_g_function;
+        rep[11] = self.suspend_out_pol;
+        rep[12] = self.wakeup_int;
+        // trigger type in bit 0-1 and level duration in bit 2-3
+        rep[13] = self.interrupt_trigger | self.interrupt_duration;
+        rep[14] = self.power_saving;
+        rep
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UartSetting {
+    baud_rate: u32,
+    data_bits: u8,
+    parity: u8,
+    stop_bit: u8,
+    breaking: u8,
+    dcd_ri_status: u8,
+}
+
+impl UartSetting {
+    fn power_on() -> Self {
+        Self {
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: 0,
+            stop_bit: 0,
+            breaking: 0,
+            dcd_ri_status: 0,
+        }
+    }
+}
+
+/// GPIO pin states, bits 0-5 for GPIO0-5 and bits 6-13 for GPIOA-H
+#[derive(Debug, Default, Clone, Copy)]
+struct GpioState {
+    out_val: u16,
+    dir: u16,
+    pull_up: u8,
+}
+
+struct State {
+    system: System,
+    chip_code: [u8; 4],
+    i2c_kbps: u16,
+    i2c_status: u8,
+    i2c_targets: BTreeMap<u8, Box<dyn I2cTarget>>,
+    /// address of the target in an on-going transaction
+    i2c_active: Option<u8>,
+    uart: UartSetting,
+    uart_loopback: bool,
+    /// level driven into INTRIN (GPIO3) from outside
+    intrin: bool,
+    /// bytes transmitted to a peer
+    uart_tx: VecDeque<u8>,
+    gpio: GpioState,
+    wires: Vec<(u16, u16)>,
+    /// parameters of the last System Setting request for each request code
+    requests: BTreeMap<u8, Vec<u8>>,
+    /// input reports queued for each interface
+    inputs: [VecDeque<Vec<u8>>; 2],
+    plugged: bool,
+    /// incremented on every unplug to invalidate interfaces of the previous connection
+    generation: u64,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    arrived: Condvar,
+}
+
+/// In-process software model of FT260 chip.
+/// It answers feature, output and input reports as FT260 does,
+/// and `SimInterface` given by `interface` can be used as `Transport` to create `Device`.
+///
+/// ```
+/// use ft260hid::device::Device;
+/// use ft260hid::sim::{Eeprom, Simulator};
+///
+/// let sim = Simulator::new();
+/// sim.attach_i2c(0x50, Eeprom::at24c02());
+/// let dev = Device::from_transport(sim.interface(0));
+/// ```
+#[derive(Clone)]
+pub struct Simulator {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for Simulator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let st = self.state();
+        f.debug_struct("Simulator")
+            .field("system", &st.system)
+            .field("i2c_targets", &st.i2c_targets.keys().collect::<Vec<_>>())
+            .field("uart", &st.uart)
+            .field("gpio", &st.gpio)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pin_bit(pin: Pin) -> u16 {
+    match pin {
+        Pin::Gpio0 => 1 << 0,
+        Pin::Gpio1 => 1 << 1,
+        Pin::Gpio2 => 1 << 2,
+        Pin::Gpio3 => 1 << 3,
+        Pin::Gpio4 => 1 << 4,
+        Pin::Gpio5 => 1 << 5,
+        Pin::GpioA => 1 << 6,
+        Pin::GpioB => 1 << 7,
+        Pin::GpioC => 1 << 8,
+        Pin::GpioD => 1 << 9,
+        Pin::GpioE => 1 << 10,
+        Pin::GpioF => 1 << 11,
+        Pin::GpioG => 1 << 12,
+        Pin::GpioH => 1 << 13,
+    }
+}
+
+/// Report ID of I2C/UART data report (`base` = 0xD0 or 0xF0) to carry `len` bytes
+fn data_report_id(base: u8, len: usize) -> u8 {
+    if len == 0 {
+        base
+    } else {
+        base + ((len - 1) / 4) as u8
+    }
+}
+
+fn sim_error(message: &str) -> Ft260Error {
+    Ft260Error::HidError {
+        message: format!("simulator: {}", message),
+    }
+}
+
+fn fill(buf: &mut [u8], report: &[u8]) -> usize {
+    let n = buf.len().min(report.len());
+    buf[..n].copy_from_slice(&report[..n]);
+    n
+}
+
+fn u16_le(data: &[u8], idx: usize) -> u16 {
+    (data[idx] as u16) | ((data[idx + 1] as u16) << 8)
+}
+
+fn u32_le(data: &[u8], idx: usize) -> u32 {
+    (data[idx] as u32)
+        | ((data[idx + 1] as u32) << 8)
+        | ((data[idx + 2] as u32) << 16)
+        | ((data[idx + 3] as u32) << 24)
+}
+
+impl State {
+    fn i2c_interface(&self) -> usize {
+        0
+    }
+
+    fn uart_interface(&self) -> usize {
+        // UART is on interface 0 only in UART mode (DCNF1=1, DCNF0=0)
+        if self.system.chip_mode == 0x02 {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Queue input reports carrying `data` separated by the maximum payload size
+    fn push_data_reports(&mut self, interface: usize, base: u8, data: &[u8]) {
+        for chunk in data.chunks(PAYLOAD_SIZE_MAX) {
+            let mut rep = vec![0u8; REPORT_SIZE];
+            rep[0] = data_report_id(base, chunk.len());
+            rep[1] = chunk.len() as u8;
+            rep[2..2 + chunk.len()].copy_from_slice(chunk);
+            self.inputs[interface].push_back(rep);
+        }
+    }
+
+    fn pin_level(&self, bit: u16) -> bool {
+        let gpio = &self.gpio;
+        if gpio.dir & bit != 0 {
+            return gpio.out_val & bit != 0;
+        }
+        for &(a, b) in &self.wires {
+            let other = if a == bit {
+                b
+            } else if b == bit {
+                a
+            } else {
+                continue;
+            };
+            if gpio.dir & other != 0 {
+                return gpio.out_val & other != 0;
+            }
+        }
+        // undriven input follows its pull-up (only GPIO0-5 have it)
+        bit < 0x40 && (gpio.pull_up as u16) & bit != 0
+    }
+
+    fn gpio_report(&self) -> [u8; 5] {
+        let mut levels = 0u16;
+        for i in 0..14 {
+            if self.pin_level(1 << i) {
+                levels |= 1 << i;
+            }
+        }
+        [
+            0xB0,
+            (levels & 0x3F) as u8,
+            (self.gpio.dir & 0x3F) as u8,
+            (levels >> 6) as u8,
+            (self.gpio.dir >> 6) as u8,
+        ]
+    }
+
+    fn set_gpio(&mut self, data: &[u8]) -> Ft260Result<()> {
+        if data.len() < 5 {
+            return Err(sim_error("GPIO write request is short"));
+        }
+        self.gpio.out_val = (data[1] as u16 & 0x3F) | ((data[3] as u16) << 6);
+        self.gpio.dir = (data[2] as u16 & 0x3F) | ((data[4] as u16) << 6);
+        Ok(())
+    }
+
+    fn i2c_report(&self) -> [u8; 5] {
+        [
+            0xC0,
+            self.i2c_status,
+            (self.i2c_kbps & 0xFF) as u8,
+            (self.i2c_kbps >> 8) as u8,
+            0,
+        ]
+    }
+
+    fn uart_report(&self) -> [u8; 10] {
+        let baud = self.uart.baud_rate;
+        [
+            0xE0,
+            self.system.uart_mode,
+            (baud & 0xFF) as u8,
+            ((baud >> 8) & 0xFF) as u8,
+            ((baud >> 16) & 0xFF) as u8,
+            ((baud >> 24) & 0xFF) as u8,
+            self.uart.data_bits,
+            self.uart.parity,
+            self.uart.stop_bit,
+            self.uart.breaking,
+        ]
+    }
+
+    /// Begin a transfer on I2C bus, returns `false` if the address was not acknowledged
+    fn i2c_start(&mut self, addr: u8, read: bool) -> bool {
+        if let Some(prev) = self.i2c_active.take() {
+            if prev != addr {
+                if let Some(t) = self.i2c_targets.get_mut(&prev) {
+                    t.stop();
+                }
+            }
+        }
+        if self.system.i2c_enable == 0 {
+            self.i2c_status = I2C_STATUS_ERROR | I2C_STATUS_CONTROLLER_IDLE;
+            return false;
+        }
+        match self.i2c_targets.get_mut(&addr) {
+            Some(t) => {
+                t.start(read);
+                self.i2c_active = Some(addr);
+                true
+            }
+            None => {
+                self.i2c_status =
+                    I2C_STATUS_ERROR | I2C_STATUS_ADDRESS_NACK | I2C_STATUS_CONTROLLER_IDLE;
+                false
+            }
+        }
+    }
+
+    fn i2c_stop(&mut self) {
+        if let Some(addr) = self.i2c_active.take() {
+            if let Some(t) = self.i2c_targets.get_mut(&addr) {
+                t.stop();
+            }
+        }
+    }
+
+    /// Finish a transfer updating the bus status
+    fn i2c_end(&mut self, stop: bool) {
+        if stop {
+            self.i2c_stop();
+            self.i2c_status = I2C_STATUS_CONTROLLER_IDLE;
+        } else {
+            self.i2c_status = I2C_STATUS_CONTROLLER_IDLE | I2C_STATUS_BUS_BUSY;
+        }
+    }
+
+    /// I2C Write Request (0xD0-0xDE)
+    fn i2c_write(&mut self, data: &[u8]) -> Ft260Result<()> {
+        if data.len() < 4 {
+            return Err(sim_error("I2C write request is short"));
+        }
+        let (addr, flag, len) = (data[1], data[2], data[3] as usize);
+        let capacity = ((data[0] - 0xD0) as usize + 1) * 4;
+        if len > capacity || 4 + len > data.len() {
+            return Err(sim_error("I2C write request has invalid length"));
+        }
+        if flag & I2C_FLAG_START != 0 {
+            if !self.i2c_start(addr, false) {
+                return Ok(());
+            }
+        } else if self.i2c_active != Some(addr) {
+            self.i2c_status = I2C_STATUS_ERROR | I2C_STATUS_CONTROLLER_IDLE;
+            return Ok(());
+        }
+        let target = self.i2c_targets.get_mut(&addr).unwrap();
+        for &b in &data[4..4 + len] {
+            if !target.write(b) {
+                self.i2c_stop();
+                self.i2c_status =
+                    I2C_STATUS_ERROR | I2C_STATUS_DATA_NACK | I2C_STATUS_CONTROLLER_IDLE;
+                return Ok(());
+            }
+        }
+        self.i2c_end(flag & I2C_FLAG_STOP != 0);
+        Ok(())
+    }
+
+    /// I2C Read Request (0xC2)
+    fn i2c_read(&mut self, interface: usize, data: &[u8]) -> Ft260Result<()> {
+        if data.len() < 5 {
+            return Err(sim_error("I2C read request is short"));
+        }
+        let (addr, flag, len) = (data[1], data[2], u16_le(data, 3) as usize);
+        if flag & I2C_FLAG_START != 0 {
+            if !self.i2c_start(addr, true) {
+                return Ok(());
+            }
+        } else if self.i2c_active != Some(addr) {
+            self.i2c_status = I2C_STATUS_ERROR | I2C_STATUS_CONTROLLER_IDLE;
+            return Ok(());
+        }
+        let target = self.i2c_targets.get_mut(&addr).unwrap();
+        let bytes: Vec<u8> = (0..len).map(|_| target.read()).collect();
+        self.push_data_reports(interface, 0xD0, &bytes);
+        self.i2c_end(flag & I2C_FLAG_STOP != 0);
+        Ok(())
+    }
+
+    /// UART Write Request (0xF0-0xFE)
+    fn uart_write(&mut self, data: &[u8]) -> Ft260Result<()> {
+        if data.len() < 2 {
+            return Err(sim_error("UART write request is short"));
+        }
+        let len = data[1] as usize;
+        let capacity = ((data[0] - 0xF0) as usize + 1) * 4;
+        if len > capacity || 2 + len > data.len() {
+            return Err(sim_error("UART write request has invalid length"));
+        }
+        if self.system.uart_mode == 0 {
+            // UART pins are working as GPIO
+            return Ok(());
+        }
+        let bytes = &data[2..2 + len];
+        if self.uart_loopback {
+            let interface = self.uart_interface();
+            self.push_data_reports(interface, 0xF0, bytes);
+        } else {
+            self.uart_tx.extend(bytes);
+        }
+        Ok(())
+    }
+
+    /// Change the level of INTRIN, queueing Interrupt Status report (0xB1) if it triggers
+    fn drive_intrin(&mut self, high: bool) {
+        let prev = std::mem::replace(&mut self.intrin, high);
+        if self.system.wakeup_int == 0 || prev == high {
+            return;
+        }
+        // rising edge and high level, or falling edge and low level
+        let on_high = self.system.interrupt_trigger < 2;
+        if high == on_high {
+            let interface = self.uart_interface();
+            let report = vec![0xB1, 0x01, self.uart.dcd_ri_status];
+            self.inputs[interface].push_back(report);
+        }
+    }
+
+    /// System Setting requests (0xA1)
+    fn system_setting(&mut self, data: &[u8]) -> Ft260Result<()> {
+        if data.len() < 2 {
+            return Err(sim_error("System Setting request is short"));
+        }
+        let arg = |n: usize| -> Ft260Result<u8> {
+            data.get(2 + n)
+                .copied()
+                .ok_or_else(|| sim_error("System Setting request lacks parameters"))
+        };
+        match data[1] {
+            0x01 => self.system.clock = arg(0)?,
+            0x02 => self.system.i2c_enable = arg(0)?,
+            0x03 => self.system.uart_mode = arg(0)?,
+            0x05 => self.system.wakeup_int = arg(0)?,
+            0x06 => self.system.gpio2_function = arg(0)?,
+            0x08 => self.system.gpio_a_function = arg(0)?,
+            0x09 => self.system.gpio_g_function = arg(0)?,
+            0x0A => {
+                self.system.interrupt_trigger = arg(0)?;
+                self.system.interrupt_duration = arg(1)?;
+            }
+            0x0B => self.system.suspend_out_pol = arg(0)?,
+            0x20 => {
+                self.i2c_stop();
+                self.i2c_status = I2C_STATUS_CONTROLLER_IDLE;
+            }
+            0x22 => self.i2c_kbps = (arg(0)? as u16) | ((arg(1)? as u16) << 8),
+            0x40 => {
+                let interface = self.uart_interface();
+                self.inputs[interface].retain(|rep| rep[0] & 0xF0 != 0xF0);
+                self.uart_tx.clear();
+            }
+            0x41 => {
+                if data.len() < 11 {
+                    return Err(sim_error("UART configuration request is short"));
+                }
+                self.system.uart_mode = data[2];
+                self.uart.baud_rate = u32_le(data, 3);
+                self.uart.data_bits = data[7];
+                self.uart.parity = data[8];
+                self.uart.stop_bit = data[9];
+                self.uart.breaking = data[10];
+            }
+            0x42 => {
+                arg(3)?;
+                self.uart.baud_rate = u32_le(data, 2);
+            }
+            0x43 => self.uart.data_bits = arg(0)?,
+            0x44 => self.uart.parity = arg(0)?,
+            0x45 => self.uart.stop_bit = arg(0)?,
+            0x46 => self.uart.breaking = arg(0)?,
+            0x61 => {
+                let mask = arg(0)? & 0x3F;
+                self.gpio.pull_up |= mask;
+            }
+            0x63 => {
+                let mask = arg(0)? & 0x3F;
+                self.gpio.pull_up &= !mask;
+            }
+            // settings without any status to read back
+            0x07 | 0x0C | 0x0D | 0x10 | 0x11 | 0x49 | 0x50..=0x53 | 0x62 | 0x64 | 0x65 => {
+                arg(0)?;
+            }
+            req => {
+                return Err(sim_error(&format!(
+                    "unknown System Setting request {:#x}",
+                    req
+                )))
+            }
+        }
+        self.requests.insert(data[1], data[2..].to_vec());
+        Ok(())
+    }
+}
+
+impl Simulator {
+    /// Create a simulated FT260 in the default state.
+    /// Both I2C (interface 0) and UART (interface 1) are available as `DCNF0=1` and `DCNF1=1`.
+    pub fn new() -> Self {
+        let state = State {
+            system: System::power_on(0x03),
+            chip_code: [0x02, 0x60, 0x02, 0x00],
+            i2c_kbps: 100,
+            i2c_status: I2C_STATUS_CONTROLLER_IDLE,
+            i2c_targets: BTreeMap::new(),
+            i2c_active: None,
+            uart: UartSetting::power_on(),
+            uart_loopback: false,
+            intrin: false,
+            uart_tx: VecDeque::new(),
+            gpio: GpioState::default(),
+            wires: Vec::new(),
+            requests: BTreeMap::new(),
+            inputs: [VecDeque::new(), VecDeque::new()],
+            plugged: true,
+            generation: 0,
+        };
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(state),
+                arrived: Condvar::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().unwrap()
+    }
+
+    /// Change the chip mode as if `DCNF0` and `DCNF1` were strapped for `mode`
+    pub fn set_chip_mode(&self, mode: ChipMode) {
+        self.state().system.chip_mode = match mode {
+            ChipMode::All => 0x00,
+            ChipMode::I2c => 0x01,
+            ChipMode::Uart => 0x02,
+            ChipMode::Both => 0x03,
+        };
+    }
+
+    /// Change the firmware version reported in Chip Code
+    pub fn set_firmware(&self, firmware: FirmwareVersion) {
+        let mut st = self.state();
+        st.chip_code[2] = firmware.minor_version;
+        st.chip_code[3] = firmware.revision;
+    }
+
+    /// `Transport` of HID interface `0` or `1` to create `Device` with `Device::from_transport`.
+    /// It stops working when the simulated chip is unplugged, even after it is plugged again.
+    pub fn interface(&self, number: usize) -> SimInterface {
+        assert!(number < 2, "FT260 has interface 0 and 1 only");
+        SimInterface {
+            sim: self.clone(),
+            number,
+            generation: self.state().generation,
+        }
+    }
+
+    /// Unplug the simulated chip from USB.
+    /// Interfaces in use fail, and the chip loses its settings as it is powered off.
+    /// Attached I2C targets, wires, chip mode and firmware version are kept.
+    pub fn unplug(&self) {
+        let mut st = self.state();
+        st.plugged = false;
+        st.generation += 1;
+        st.system = System::power_on(st.system.chip_mode);
+        st.i2c_kbps = 100;
+        st.i2c_status = I2C_STATUS_CONTROLLER_IDLE;
+        st.i2c_active = None;
+        st.uart = UartSetting::power_on();
+        st.uart_tx.clear();
+        st.gpio = GpioState::default();
+        st.requests.clear();
+        st.inputs = [VecDeque::new(), VecDeque::new()];
+        drop(st);
+        self.inner.arrived.notify_all();
+    }
+
+    /// Plug the simulated chip into USB again.
+    /// New interfaces should be created with `interface` to use it.
+    pub fn plug(&self) {
+        self.state().plugged = true;
+    }
+
+    /// If the simulated chip is plugged into USB
+    pub fn is_plugged(&self) -> bool {
+        self.state().plugged
+    }
+
+    /// Suspend the simulated chip as the USB host does, or resume it.
+    /// PWREN is deasserted while it is suspended.
+    pub fn set_suspended(&self, suspended: bool) {
+        let mut st = self.state();
+        st.system.suspend_status = suspended as u8;
+        st.system.pwren_status = !suspended as u8;
+    }
+
+    /// Enable power saving mode as if it were configured in EEPROM
+    pub fn set_power_saving(&self, enable: bool) {
+        self.state().system.power_saving = enable as u8;
+    }
+
+    /// Attach an I2C target model at 7-bit address `addr`, replacing a target already attached there
+    pub fn attach_i2c<T: I2cTarget + 'static>(&self, addr: u8, target: T) {
+        self.state().i2c_targets.insert(addr, Box::new(target));
+    }
+
+    /// Detach the I2C target at 7-bit address `addr`
+    pub fn detach_i2c(&self, addr: u8) {
+        let mut st = self.state();
+        if st.i2c_active == Some(addr) {
+            st.i2c_active = None;
+        }
+        st.i2c_targets.remove(&addr);
+    }
+
+    /// Drive INTRIN (GPIO3) at `high` level from outside.
+    /// While GPIO3 works as INTRIN, a change into the trigger condition is reported as an interrupt.
+    /// Level duration is not simulated, and a level trigger is reported once on entering the level.
+    pub fn set_intrin(&self, high: bool) {
+        self.state().drive_intrin(high);
+        self.inner.arrived.notify_all();
+    }
+
+    /// Connect TXD to RXD, or disconnect them to talk with `UartPeer`
+    pub fn set_uart_loopback(&self, enable: bool) {
+        self.state().uart_loopback = enable;
+    }
+
+    /// Byte pipe to the other end of UART, used while loopback is disabled
+    pub fn uart_peer(&self) -> UartPeer {
+        UartPeer { sim: self.clone() }
+    }
+
+    /// Short-circuit two GPIO pins.
+    /// An input pin reads the level of an output pin wired with it.
+    pub fn wire(&self, a: Pin, b: Pin) {
+        self.state().wires.push((pin_bit(a), pin_bit(b)));
+    }
+
+    /// Remove all wires added by `wire`
+    pub fn unwire_all(&self) {
+        self.state().wires.clear();
+    }
+
+    /// Parameters following the request code in the last System Setting (0xA1) request of `request`.
+    /// Settings which can't be read back from the chip can be checked with it.
+    pub fn last_request(&self, request: u8) -> Option<Vec<u8>> {
+        self.state().requests.get(&request).cloned()
+    }
+}
+
+/// The other end of the simulated UART
+#[derive(Debug, Clone)]
+pub struct UartPeer {
+    sim: Simulator,
+}
+
+impl UartPeer {
+    /// Send bytes into RXD of the simulated FT260
+    pub fn write(&self, data: &[u8]) {
+        let mut st = self.sim.state();
+        if st.system.uart_mode == 0 {
+            return;
+        }
+        let interface = st.uart_interface();
+        st.push_data_reports(interface, 0xF0, data);
+        drop(st);
+        self.sim.inner.arrived.notify_all();
+    }
+
+    /// Take all bytes transmitted from TXD of the simulated FT260
+    pub fn read(&self) -> Vec<u8> {
+        self.sim.state().uart_tx.drain(..).collect()
+    }
+}
+
+/// HID interface of `Simulator` implementing `Transport`
+#[derive(Debug, Clone)]
+pub struct SimInterface {
+    sim: Simulator,
+    number: usize,
+    generation: u64,
+}
+
+impl SimInterface {
+    /// Lock the state of the chip if this interface is still connected to it
+    fn state(&self) -> Ft260Result<MutexGuard<'_, State>> {
+        let st = self.sim.state();
+        self.check(&st)?;
+        Ok(st)
+    }
+
+    fn check(&self, st: &State) -> Ft260Result<()> {
+        if st.plugged && st.generation == self.generation {
+            Ok(())
+        } else {
+            Err(Ft260Error::Disconnected {
+                message: "simulator: device was unplugged".to_string(),
+            })
+        }
+    }
+}
+
+impl Transport for SimInterface {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        let inner = &self.sim.inner;
+        let mut st = inner.state.lock().unwrap();
+        loop {
+            self.check(&st)?;
+            if let Some(rep) = st.inputs[self.number].pop_front() {
+                return Ok(fill(buf, &rep));
+            }
+            if timeout == 0 {
+                return Ok(0);
+            }
+            if timeout < 0 {
+                st = inner.arrived.wait(st).unwrap();
+            } else {
+                let dur = Duration::from_millis(timeout as u64);
+                let (guard, res) = inner.arrived.wait_timeout(st, dur).unwrap();
+                st = guard;
+                if res.timed_out() && st.inputs[self.number].is_empty() {
+                    return Ok(0);
+                }
+            }
+        }
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        if data.is_empty() {
+            return Err(sim_error("empty output report"));
+        }
+        let mut st = self.state()?;
+        match data[0] {
+            0xD0..=0xDE => st.i2c_write(data)?,
+            0xC2 => {
+                let interface = if self.number == st.i2c_interface() {
+                    self.number
+                } else {
+                    st.i2c_interface()
+                };
+                st.i2c_read(interface, data)?
+            }
+            0xF0..=0xFE => st.uart_write(data)?,
+            id => {
+                return Err(sim_error(&format!("unknown output report {:#x}", id)));
+            }
+        }
+        drop(st);
+        self.sim.inner.arrived.notify_all();
+        Ok(data.len())
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        if buf.is_empty() {
+            return Err(sim_error("empty feature report buffer"));
+        }
+        let st = self.state()?;
+        match buf[0] {
+            0xA0 => {
+                let mut rep = [0u8; 13];
+                rep[0] = 0xA0;
+                rep[1..5].copy_from_slice(&st.chip_code);
+                Ok(fill(buf, &rep))
+            }
+            0xA1 => Ok(fill(buf, &st.system.report())),
+            0xB0 => Ok(fill(buf, &st.gpio_report())),
+            0xC0 => Ok(fill(buf, &st.i2c_report())),
+            0xE0 => Ok(fill(buf, &st.uart_report())),
+            0xE2 => Ok(fill(buf, &[0xE2, st.uart.dcd_ri_status])),
+            id => Err(sim_error(&format!("unknown feature report {:#x}", id))),
+        }
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        if data.is_empty() {
+            return Err(sim_error("empty feature report"));
+        }
+        let mut st = self.state()?;
+        match data[0] {
+            0xA1 => st.system_setting(data),
+            0xB0 => st.set_gpio(data),
+            id => Err(sim_error(&format!("unknown feature report {:#x}", id))),
+        }
+    }
+
+    fn is_present(&self) -> bool {
+        self.check(&self.sim.state()).is_ok()
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::{Arc, Mutex};
+
+/// I2C target device attachable to the simulated I2C bus of `Simulator`
+pub trait I2cTarget: Send {
+    /// START or repeated START condition addressed to this target.
+    /// `read` is `true` when the controller is going to read from the target.
+    fn start(&mut self, read: bool) {
+        let _ = read;
+    }
+
+    /// A byte written by the controller.
+    /// Returns `false` to respond NACK.
+    fn write(&mut self, byte: u8) -> bool;
+
+    /// A byte to be read by the controller.
+    fn read(&mut self) -> u8;
+
+    /// STOP condition
+    fn stop(&mut self) {}
+}
+
+#[derive(Debug)]
+struct EepromState {
+    mem: Vec<u8>,
+    page_size: usize,
+    pointer: usize,
+    /// the first byte after START for writing is the word address
+    addressing: bool,
+}
+
+/// Model of I2C serial EEPROM with 1-byte word address, such as AT24C02 mounted on UMFT260EV1A.
+/// Writes roll over within a page, and reads roll over within the whole memory as the real chip does.
+/// Clones share the same memory.
+#[derive(Debug, Clone)]
+pub struct Eeprom {
+    state: Arc<Mutex<EepromState>>,
+}
+
+impl Eeprom {
+    /// Create an EEPROM model with memory size and page size in bytes
+    pub fn new(size: usize, page_size: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(EepromState {
+                mem: vec![0xFFu8; size],
+                page_size,
+                pointer: 0,
+                addressing: false,
+            })),
+        }
+    }
+
+    /// AT24C02: 256 bytes organized in 8-byte pages
+    pub fn at24c02() -> Self {
+        Self::new(256, 8)
+    }
+
+    /// Copy of the whole memory
+    pub fn contents(&self) -> Vec<u8> {
+        self.state.lock().unwrap().mem.clone()
+    }
+
+    /// Overwrite memory from `offset`
+    pub fn load(&self, offset: usize, data: &[u8]) {
+        let mut st = self.state.lock().unwrap();
+        st.mem[offset..offset + data.len()].copy_from_slice(data);
+    }
+}
+
+impl I2cTarget for Eeprom {
+    fn start(&mut self, read: bool) {
+        self.state.lock().unwrap().addressing = !read;
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        let mut st = self.state.lock().unwrap();
+        let size = st.mem.len();
+        if st.addressing {
+            st.pointer = byte as usize % size;
+            st.addressing = false;
+        } else {
+            let ptr = st.pointer;
+            st.mem[ptr] = byte;
+            // roll over within the page
+            let page = ptr - ptr % st.page_size;
+            st.pointer = page + (ptr + 1) % st.page_size;
+        }
+        true
+    }
+
+    fn read(&mut self) -> u8 {
+        let mut st = self.state.lock().unwrap();
+        let ptr = st.pointer;
+        st.pointer = (ptr + 1) % st.mem.len();
+        st.mem[ptr]
+    }
+}
+
+#[derive(Debug)]
+struct RegisterFileState {
+    regs: Vec<u8>,
+    pointer: usize,
+    addressing: bool,
+}
+
+/// Model of generic I2C device with 8-bit register address and auto-increment.
+/// Clones share the same registers.
+#[derive(Debug, Clone)]
+pub struct RegisterFile {
+    state: Arc<Mutex<RegisterFileState>>,
+}
+
+impl RegisterFile {
+    /// Create a register file with `size` registers initialized as zero
+    pub fn new(size: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RegisterFileState {
+                regs: vec![0u8; size],
+                pointer: 0,
+                addressing: false,
+            })),
+        }
+    }
+
+    /// Get a register value
+    pub fn get(&self, reg: u8) -> u8 {
+        self.state.lock().unwrap().regs[reg as usize]
+    }
+
+    /// Set a register value
+    pub fn set(&self, reg: u8, val: u8) {
+        self.state.lock().unwrap().regs[reg as usize] = val;
+    }
+}
+
+impl I2cTarget for RegisterFile {
+    fn start(&mut self, read: bool) {
+        self.state.lock().unwrap().addressing = !read;
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        let mut st = self.state.lock().unwrap();
+        if st.addressing {
+            st.addressing = false;
+            if byte as usize >= st.regs.len() {
+                return false;
+            }
+            st.pointer = byte as usize;
+            true
+        } else if st.pointer < st.regs.len() {
+            let ptr = st.pointer;
+            st.regs[ptr] = byte;
+            st.pointer += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let mut st = self.state.lock().unwrap();
+        let ptr = st.pointer;
+        if ptr < st.regs.len() {
+            st.pointer += 1;
+            st.regs[ptr]
+        } else {
+            0xFF
+        }
+    }
+}
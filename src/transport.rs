@@ -0,0 +1,48 @@
+use crate::Ft260Result;
+
+/// Low level access to one HID interface of FT260 device.
+/// `Device` talks to the chip only through this trait,
+/// so other backends than `hidapi` (test doubles, simulators, tunnels, etc.) can be plugged in.
+///
+/// Every buffer begins with the report ID byte, in the same manner as `hidapi`.
+pub trait Transport: Send + Sync {
+    /// Read an input report into `buf`, waiting up to `timeout` milliseconds.
+    /// `0` returns immediately and `-1` blocks until a report arrives.
+    /// Returns the number of bytes read, or `0` when no report was available.
+    /// It should block while waiting, as the reading thread of `Device` calls it repeatedly.
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize>;
+
+    /// Write an output report.
+    /// Returns the number of bytes written.
+    fn write(&self, data: &[u8]) -> Ft260Result<usize>;
+
+    /// Get a feature report.
+    /// `buf[0]` should be set as the report ID to get before calling.
+    /// Returns the number of bytes read into `buf`.
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize>;
+
+    /// Send a feature report.
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()>;
+
+    /// If the HID interface is still there, asked after an error which doesn't tell it by itself,
+    /// as some `hidapi` backends report USB unplug as `Ft260Error::HidError` like any other failure.
+    /// The default regards it as present.
+    fn is_present(&self) -> bool {
+        true
+    }
+
+    /// Stop waiting in operations in progress, called when `Device` is dropped
+    /// before it joins the thread reading input reports.
+    /// The default does nothing.
+    fn shutdown(&self) {}
+}
+
+/// submodule of `Transport` reconnecting automatically
+pub mod reconnect;
+
+pub use reconnect::Reconnect;
+
+/// submodule of `Transport` playing back a recorded session
+pub mod replay;
+
+pub use replay::Replay;
@@ -0,0 +1,251 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::transport::Transport;
+use crate::{Ft260Error, Ft260Result};
+
+/// Interval of retries to connect again
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+type Connect = dyn Fn() -> Ft260Result<Box<dyn Transport>> + Send + Sync;
+
+struct Connection {
+    transport: Option<Box<dyn Transport>>,
+    /// incremented on every successful connection
+    generation: u64,
+}
+
+/// `Transport` which connects again when the inner transport fails, e.g. by USB unplug.
+///
+/// Settings sent to the chip with feature reports are recorded:
+/// System Setting requests (0xA1) except resets, such as clock, pin functions, I2C speed and UART configuration,
+/// and GPIO directions and values (0xB0).
+/// They are sent again in the original order after reconnection, as the chip loses them on power cycle.
+///
+/// `Ft260Error::Disconnected` and I/O errors of `NotFound` or `BrokenPipe` from the inner transport
+/// are regarded as disconnection, and so is `Ft260Error::HidError` if `Transport::is_present` of the inner one turns `false`.
+/// The others are returned as they are.
+/// A read failed by disconnection is retried once after reconnection.
+/// A write is not sent again, as the chip may have received it,
+/// and `Ft260Error::Disconnected` is returned after reconnection for the caller to decide.
+/// It fails with `Ft260Error::Disconnected` if the chip doesn't come back within the timeout,
+/// or when `Transport::shutdown` is called while connecting again.
+///
+/// ```
+/// use std::time::Duration;
+/// use ft260hid::device::Device;
+/// use ft260hid::sim::Simulator;
+/// use ft260hid::transport::Reconnect;
+///
+/// let sim = Simulator::new();
+/// let dev = Device::from_transport(Reconnect::new(
+///     {
+///         let sim = sim.clone();
+///         move || Ok(sim.interface(0))
+///     },
+///     Duration::from_secs(10),
+/// ));
+/// ```
+pub struct Reconnect {
+    connect: Box<Connect>,
+    timeout: Duration,
+    connection: RwLock<Connection>,
+    /// held by the thread trying to connect again, without blocking others using the connection
+    connecting: Mutex<()>,
+    /// set by `shutdown` to give up connecting again
+    closed: AtomicBool,
+    /// feature reports to restore settings
+    settings: Mutex<Vec<Vec<u8>>>,
+}
+
+impl std::fmt::Debug for Reconnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reconnect")
+            .field("timeout", &self.timeout)
+            .field("settings", &self.settings)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Reconnect {
+    /// Create with a function to connect to the chip.
+    /// It is called for the first time on the first operation,
+    /// and each time the connection is lost, retried until `timeout` elapses.
+    pub fn new<T, F>(connect: F, timeout: Duration) -> Self
+    where
+        T: Transport + 'static,
+        F: Fn() -> Ft260Result<T> + Send + Sync + 'static,
+    {
+        Self {
+            connect: Box::new(move || Ok(Box::new(connect()?) as Box<dyn Transport>)),
+            timeout,
+            connection: RwLock::new(Connection {
+                transport: None,
+                generation: 0,
+            }),
+            connecting: Mutex::new(()),
+            closed: AtomicBool::new(false),
+            settings: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// If the inner transport is connected
+    pub fn is_connected(&self) -> bool {
+        self.connection.read().unwrap().transport.is_some()
+    }
+
+    /// Run `op` on the inner transport, connecting again if it fails by disconnection.
+    /// `op` is run again on the new connection only if `retry`,
+    /// otherwise the error is returned as it may have reached the chip.
+    fn call<R>(
+        &self,
+        retry: bool,
+        mut op: impl FnMut(&dyn Transport) -> Ft260Result<R>,
+    ) -> Ft260Result<R> {
+        let generation = {
+            let conn = self.connection.read().unwrap();
+            if let Some(transport) = conn.transport.as_deref() {
+                match op(transport) {
+                    Err(e) if is_lost(&e, transport) => {
+                        tracing::debug!(error = %e, "connection was lost");
+                        if !retry {
+                            let generation = conn.generation;
+                            drop(conn);
+                            self.reconnect(generation)?;
+                            return Err(match e {
+                                Ft260Error::HidError { message } => {
+                                    Ft260Error::Disconnected { message }
+                                }
+                                e => e,
+                            });
+                        }
+                    }
+                    res => return res,
+                }
+            }
+            conn.generation
+        };
+        self.reconnect(generation)?;
+        let conn = self.connection.read().unwrap();
+        match conn.transport.as_deref() {
+            Some(transport) => op(transport),
+            None => Err(Ft260Error::Disconnected {
+                message: "connection was lost again".to_string(),
+            }),
+        }
+    }
+
+    /// Connect again unless another thread has done it since `generation` failed.
+    /// The connection is locked only to replace the transport, not while retrying,
+    /// so that the other threads and `shutdown` are not held up.
+    fn reconnect(&self, generation: u64) -> Ft260Result<()> {
+        let _connecting = self.connecting.lock().unwrap();
+        {
+            let mut conn = self.connection.write().unwrap();
+            if conn.transport.is_some() && conn.generation != generation {
+                return Ok(());
+            }
+            // release the dead one before opening the same interface again
+            conn.transport = None;
+        }
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if self.closed.load(Ordering::Relaxed) {
+                return Err(Ft260Error::Disconnected {
+                    message: "shut down while connecting again".to_string(),
+                });
+            }
+            match (self.connect)().and_then(|transport| {
+                self.restore(transport.as_ref())?;
+                Ok(transport)
+            }) {
+                Ok(transport) => {
+                    let mut conn = self.connection.write().unwrap();
+                    conn.transport = Some(transport);
+                    conn.generation += 1;
+                    tracing::info!(generation = conn.generation, "connected again");
+                    return Ok(());
+                }
+                Err(e) => {
+                    if Instant::now() >= deadline {
+                        tracing::warn!(error = %e, timeout = ?self.timeout, "gave up connecting again");
+                        return Err(Ft260Error::Disconnected {
+                            message: format!(
+                                "failed to connect again in {:?}: {}",
+                                self.timeout, e
+                            ),
+                        });
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        }
+    }
+
+    fn restore(&self, transport: &dyn Transport) -> Ft260Result<()> {
+        for data in self.settings.lock().unwrap().iter() {
+            transport.send_feature_report(data)?;
+        }
+        Ok(())
+    }
+
+    /// Record a feature report sent to restore it later.
+    /// A newer report of the same setting replaces the older one.
+    fn record(&self, data: &[u8]) {
+        let key_len = match data {
+            // I2C and UART resets are not settings
+            [0xA1, 0x20, ..] | [0xA1, 0x40, ..] => return,
+            // pull-up and pull-down requests are masks to accumulate
+            [0xA1, 0x61..=0x65, ..] => data.len(),
+            [0xA1, _, ..] => 2,
+            [0xB0, ..] => 1,
+            _ => return,
+        };
+        let mut settings = self.settings.lock().unwrap();
+        settings.retain(|d| d.len() < key_len || d[..key_len] != data[..key_len]);
+        settings.push(data.to_vec());
+    }
+}
+
+/// If `e` from `transport` means the connection was lost.
+/// hidraw backend of `hidapi` fails with `HidError` on unplug as on the others,
+/// so the transport is asked if it is still there.
+fn is_lost(e: &Ft260Error, transport: &dyn Transport) -> bool {
+    e.is_disconnection() || (matches!(e, Ft260Error::HidError { .. }) && !transport.is_present())
+}
+
+impl Transport for Reconnect {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        self.call(true, |t| t.read_timeout(buf, timeout))
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        self.call(false, |t| t.write(data))
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        self.call(true, |t| t.get_feature_report(buf))
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        self.call(false, |t| t.send_feature_report(data))?;
+        self.record(data);
+        Ok(())
+    }
+
+    fn is_present(&self) -> bool {
+        match self.connection.read().unwrap().transport.as_deref() {
+            Some(transport) => transport.is_present(),
+            None => false,
+        }
+    }
+
+    fn shutdown(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        if let Some(transport) = self.connection.read().unwrap().transport.as_deref() {
+            transport.shutdown();
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::capture::{self, Direction, Entry, ReportType};
+use crate::transport::Transport;
+use crate::{Ft260Error, Ft260Result};
+
+/// Time for the reading thread to take input reports recorded before a request
+const INPUT_WAIT: Duration = Duration::from_secs(1);
+
+/// Request which didn't match the recorded session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Number of entries replayed before the request
+    pub position: usize,
+    /// Request recorded next, `None` after the end of the session
+    pub expected: Option<Entry>,
+    /// Request sent, with time since the replay started
+    pub actual: Entry,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "replay diverged at entry {}: ", self.position)?;
+        match &self.expected {
+            Some(e) => write!(f, "expected `{}`", e.to_line())?,
+            None => write!(f, "session ended")?,
+        }
+        write!(f, ", got `{}`", self.actual.to_line())
+    }
+}
+
+struct State {
+    /// entries not replayed yet
+    entries: VecDeque<Entry>,
+    replayed: usize,
+    divergences: Vec<Divergence>,
+}
+
+struct Shared {
+    interface: u8,
+    start: Instant,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// `Transport` playing back a session recorded by `capture::Capture` in `capture::Format::Text`,
+/// so that a problem seen on hardware can be reproduced as a deterministic test.
+///
+/// Feature and output reports sent are compared with the recorded ones in order,
+/// and Get Feature returns the recorded report.
+/// Recorded input reports are fed in order, each as soon as the requests recorded before it have been sent.
+///
+/// A request not matching the recorded one fails with `Ft260Error::HidError`,
+/// leaving the recorded one to be replayed, and is kept as a `Divergence`.
+/// Clones share the session, so one can be kept to check it after the device takes another.
+///
+/// ```
+/// use ft260hid::capture::Entry;
+/// use ft260hid::device::Device;
+/// use ft260hid::io::gpio::{Pin, Val};
+/// use ft260hid::transport::Replay;
+///
+/// // GPIO report returned by the chip, or `Replay::open` to read a capture file
+/// let entry = Entry::from_line("0.000100 0 < feature b0 04040000").unwrap().unwrap();
+/// let replay = Replay::from_entries(vec![entry], 0);
+/// let dev = Device::from_transport(replay.clone());
+/// assert_eq!(dev.gpio().read(Pin::Gpio2).unwrap(), Val::High);
+/// assert!(replay.is_finished());
+/// ```
+#[derive(Clone)]
+pub struct Replay {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state();
+        f.debug_struct("Replay")
+            .field("interface", &self.shared.interface)
+            .field("replayed", &state.replayed)
+            .field("remaining", &state.entries.len())
+            .field("divergences", &state.divergences.len())
+            .finish()
+    }
+}
+
+impl Replay {
+    /// Replay reports of `interface` in capture file at `path`
+    pub fn open(path: impl AsRef<Path>, interface: u8) -> Ft260Result<Self> {
+        let entries = capture::read_text(BufReader::new(File::open(path)?))?;
+        Ok(Self::from_entries(entries, interface))
+    }
+
+    /// Replay reports of `interface` among `entries`
+    pub fn from_entries(entries: impl IntoIterator<Item = Entry>, interface: u8) -> Self {
+        let entries = entries
+            .into_iter()
+            .filter(|e| e.interface == interface)
+            .collect();
+        Self {
+            shared: Arc::new(Shared {
+                interface,
+                start: Instant::now(),
+                state: Mutex::new(State {
+                    entries,
+                    replayed: 0,
+                    divergences: Vec::new(),
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Requests which didn't match the recorded ones
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.state().divergences.clone()
+    }
+
+    /// Number of entries not replayed yet
+    pub fn remaining(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// If every entry has been replayed without divergence
+    pub fn is_finished(&self) -> bool {
+        let state = self.state();
+        state.entries.is_empty() && state.divergences.is_empty()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap()
+    }
+
+    /// Find the next recorded request after the input reports before it were taken,
+    /// and remove it if `matches`
+    fn request(&self, actual: Entry, matches: impl Fn(&Entry) -> bool) -> Ft260Result<Entry> {
+        let deadline = Instant::now() + INPUT_WAIT;
+        let mut state = self.state();
+        let is_input = |e: &Entry| e.report_type == ReportType::Input;
+        while state.entries.front().is_some_and(is_input) {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                break;
+            }
+            state = self.shared.changed.wait_timeout(state, timeout).unwrap().0;
+        }
+        let index = state.entries.iter().position(|e| !is_input(e));
+        if let Some(i) = index.filter(|&i| matches(&state.entries[i])) {
+            let entry = state.entries.remove(i).unwrap();
+            state.replayed += 1;
+            self.shared.changed.notify_all();
+            return Ok(entry);
+        }
+        let divergence = Divergence {
+            position: state.replayed,
+            expected: index.map(|i| state.entries[i].clone()),
+            actual,
+        };
+        tracing::warn!(%divergence, "replay diverged");
+        let message = divergence.to_string();
+        state.divergences.push(divergence);
+        Err(Ft260Error::HidError { message })
+    }
+
+    fn entry(&self, direction: Direction, report_type: ReportType, data: &[u8]) -> Entry {
+        Entry {
+            timestamp: self.shared.start.elapsed(),
+            interface: self.shared.interface,
+            direction,
+            report_type,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Compare a report sent with the recorded one
+    fn send(&self, report_type: ReportType, data: &[u8]) -> Ft260Result<()> {
+        let actual = self.entry(Direction::ToDevice, report_type, data);
+        self.request(actual, |e| {
+            e.direction == Direction::ToDevice && e.report_type == report_type && e.data == data
+        })?;
+        Ok(())
+    }
+}
+
+impl Transport for Replay {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        let deadline = u64::try_from(timeout)
+            .ok()
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+        let mut state = self.state();
+        loop {
+            if state
+                .entries
+                .front()
+                .is_some_and(|e| e.report_type == ReportType::Input)
+            {
+                let entry = state.entries.pop_front().unwrap();
+                state.replayed += 1;
+                self.shared.changed.notify_all();
+                let len = entry.data.len().min(buf.len());
+                buf[..len].copy_from_slice(&entry.data[..len]);
+                return Ok(len);
+            }
+            state = match deadline {
+                None => self.shared.changed.wait(state).unwrap(),
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    if timeout.is_zero() {
+                        return Ok(0);
+                    }
+                    self.shared.changed.wait_timeout(state, timeout).unwrap().0
+                }
+            };
+        }
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        self.send(ReportType::Output, data)?;
+        Ok(data.len())
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        let id = buf[0];
+        let actual = self.entry(Direction::FromDevice, ReportType::Feature, &[id]);
+        let entry = self.request(actual, |e| {
+            e.direction == Direction::FromDevice
+                && e.report_type == ReportType::Feature
+                && e.data.first() == Some(&id)
+        })?;
+        let len = entry.data.len().min(buf.len());
+        buf[..len].copy_from_slice(&entry.data[..len]);
+        Ok(len)
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        self.send(ReportType::Feature, data)
+    }
+}
@@ -0,0 +1,171 @@
+//!
+//! Tests of async API with `tokio` feature, running on `sim::Simulator`.
+//!
+#![cfg(feature = "tokio")]
+
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Dir, Group, Pin, Val};
+use ft260hid::io::{i2c, uart};
+use ft260hid::sim::{Eeprom, RegisterFile, Simulator};
+
+use crate::common::SlowInterface;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+const EEPROM_ADDRESS: u8 = 0x50;
+
+#[tokio::test]
+async fn async_i2c_eeprom() {
+    let sim = Simulator::new();
+    let eeprom = Eeprom::at24c02();
+    sim.attach_i2c(EEPROM_ADDRESS, eeprom.clone());
+    let dev = Device::from_transport(sim.interface(0));
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+
+    let data = [0x10u8, 0x11, 0x12, 0x13];
+    assert_eq!(
+        i2c.write_async(
+            EEPROM_ADDRESS,
+            i2c::Flag::StartAndStop,
+            &[0x08, 0x10, 0x11, 0x12, 0x13],
+            5
+        )
+        .await
+        .unwrap(),
+        5
+    );
+    assert_eq!(&eeprom.contents()[0x08..0x0C], &data);
+
+    let mut buf = [0u8; 4];
+    assert!(i2c
+        .write_read_async(
+            EEPROM_ADDRESS,
+            &[0x08],
+            1,
+            &mut buf,
+            4,
+            i2c::DURATION_WAIT_DEFAULT
+        )
+        .await
+        .is_ok());
+    assert_eq!(buf, data);
+    assert_eq!(i2c.is_idle_async().await, Some(true));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn async_i2c_tasks() {
+    let sim = Simulator::new();
+    let regs = RegisterFile::new(16);
+    for reg in 0..16u8 {
+        regs.set(reg, !reg);
+    }
+    sim.attach_i2c(0x20, regs);
+    let i2c = Device::from_transport(sim.interface(0))
+        .i2c()
+        .init(i2c::KBPS_DEFAULT)
+        .unwrap();
+    let i2c = Arc::new(i2c);
+
+    let tasks: Vec<_> = (0..8u8)
+        .map(|n| {
+            let i2c = i2c.clone();
+            tokio::spawn(async move {
+                let reg = n * 2;
+                let mut buf = [0u8; 2];
+                i2c.write_read_async(0x20, &[reg], 1, &mut buf, 2, i2c::DURATION_WAIT_DEFAULT)
+                    .await
+                    .unwrap();
+                assert_eq!(buf, [!reg, !(reg + 1)]);
+            })
+        })
+        .collect();
+    for t in tasks {
+        t.await.unwrap();
+    }
+}
+
+// a single executor thread keeps running other tasks while a read waits for data
+#[tokio::test(flavor = "current_thread")]
+async fn async_uart_wait() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = Device::from_transport(sim.interface(1));
+    let uart = dev.uart().init().unwrap();
+
+    let mut buf = [0u8; 4];
+    let (res, _) = tokio::join!(
+        uart.read_async(&mut buf, 4, uart::DURATION_WAIT_DEFAULT),
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            peer.write(b"late");
+        }
+    );
+    assert_eq!(res.unwrap(), 4);
+    assert_eq!(&buf, b"late");
+
+    // timeout
+    assert_eq!(
+        uart.read_async(&mut buf, 4, Duration::from_millis(100))
+            .await
+            .unwrap(),
+        0
+    );
+
+    assert_eq!(uart.write_async(b"ping", 4).await.unwrap(), 4);
+    assert_eq!(peer.read(), b"ping");
+}
+
+#[tokio::test]
+async fn async_gpio_wired() {
+    let sim = Simulator::new();
+    sim.wire(Pin::GpioC, Pin::GpioD);
+    let dev = Device::from_transport(sim.interface(0));
+    let gpio = dev.gpio();
+    assert!(gpio.enable_pin_async(Group::Gpio_B_C_D_E_F_H).await.is_ok());
+    assert!(gpio.set_dir_async(Pin::GpioC, Dir::In).await.is_ok());
+    assert!(gpio.set_dir_async(Pin::GpioD, Dir::Out).await.is_ok());
+    for v in [Val::High, Val::Low] {
+        assert!(gpio.write_async(Pin::GpioD, v).await.is_ok());
+        assert_eq!(gpio.read_async(Pin::GpioC).await.unwrap(), v);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn async_gpio_concurrent() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(SlowInterface(sim.interface(0)));
+    let gpio = Arc::new(dev.gpio());
+    assert!(gpio.enable_pin_async(Group::Gpio_B_C_D_E_F_H).await.is_ok());
+
+    // async tasks and a thread change pins of one report together
+    let thread = std::thread::spawn({
+        let gpio = gpio.clone();
+        move || {
+            for _ in 0..10 {
+                assert!(gpio.set_dir(Pin::GpioB, Dir::Out).is_ok());
+                assert!(gpio.write(Pin::GpioB, Val::High).is_ok());
+                assert_eq!(gpio.read(Pin::GpioB).unwrap(), Val::High);
+            }
+        }
+    });
+    let tasks: Vec<_> = [Pin::GpioC, Pin::GpioD]
+        .into_iter()
+        .map(|pin| {
+            let gpio = gpio.clone();
+            tokio::spawn(async move {
+                for _ in 0..10 {
+                    assert!(gpio.set_dir_async(pin, Dir::Out).await.is_ok());
+                    assert!(gpio.write_async(pin, Val::High).await.is_ok());
+                    assert_eq!(gpio.read_async(pin).await.unwrap(), Val::High);
+                    assert!(gpio.write_async(pin, Val::Low).await.is_ok());
+                    assert_eq!(gpio.read_async(pin).await.unwrap(), Val::Low);
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    thread.join().unwrap();
+}
@@ -0,0 +1,163 @@
+//!
+//! Tests of report capture, running on `sim::Simulator`.
+//!
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ft260hid::capture::{Capture, Entry, Format};
+use ft260hid::device::Device;
+use ft260hid::io::i2c;
+use ft260hid::sim::{RegisterFile, Simulator};
+
+use crate::common::sim_chip;
+
+/// Writer collecting captured bytes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn bytes(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+}
+
+#[test]
+fn capture_text() {
+    let sim = Simulator::new();
+    sim.attach_i2c(0x20, RegisterFile::new(16));
+    let dev = Device::from_transport(sim.interface(0));
+    let captured = Captured::default();
+    let capture = Capture::new(captured.clone(), Format::Text).unwrap();
+    dev.start_capture(&capture, 0);
+
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+    let mut buf = [0u8; 2];
+    assert!(i2c
+        .write_read(0x20, &[0], 1, &mut buf, 2, i2c::DURATION_WAIT_DEFAULT)
+        .is_ok());
+    dev.stop_capture();
+    let len = captured.bytes().len();
+    assert!(i2c.status().is_ok());
+    assert_eq!(captured.bytes().len(), len);
+
+    let text = String::from_utf8(captured.bytes()).unwrap();
+    let mut lines = text.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("# ft260hid capture 1, started at "));
+    let entries: Vec<Vec<&str>> = lines.map(|l| l.split(' ').collect()).collect();
+    assert!(entries.iter().all(|e| e.len() == 6 && e[1] == "0"));
+    // I2C clock speed request
+    assert!(entries
+        .iter()
+        .any(|e| e[2..5] == [">", "feature", "a1"] && e[5] == "226400"));
+    // write of register address and read request, sent in reports padded to the maximum length
+    assert!(entries
+        .iter()
+        .any(|e| e[2..5] == [">", "output", "d0"] && e[5].starts_with("20020100")));
+    assert!(entries
+        .iter()
+        .any(|e| e[2..5] == [">", "output", "c2"] && e[5].starts_with("20070200")));
+    assert!(entries
+        .iter()
+        .any(|e| e[2..5] == ["<", "input", "d0"] && e[5].starts_with("02")));
+    // feature report returned by the chip
+    assert!(entries.iter().any(|e| e[2..5] == ["<", "feature", "c0"]));
+    // timestamps are monotonic
+    let times: Vec<f64> = entries.iter().map(|e| e[0].parse().unwrap()).collect();
+    assert!(times.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn capture_pcapng() {
+    let sim = Simulator::new();
+    sim.set_uart_loopback(true);
+    let chip = sim_chip(&sim, 2).unwrap();
+    let path = std::env::temp_dir().join(format!("ft260hid-capture-{}.pcapng", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let capture = Capture::create(&path, Format::Pcapng).unwrap();
+    chip.start_capture(&capture);
+
+    let uart = chip.uart().unwrap().init().unwrap();
+    assert_eq!(uart.write(b"abc", 3).unwrap(), 3);
+    let mut buf = [0u8; 3];
+    assert_eq!(
+        uart.read(&mut buf, 3, ft260hid::io::uart::DURATION_WAIT_DEFAULT)
+            .unwrap(),
+        3
+    );
+    chip.stop_capture();
+    drop(capture);
+
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    // Section Header Block
+    assert_eq!(u32_at(&bytes, 0), 0x0A0D0D0A);
+    assert_eq!(u32_at(&bytes, 8), 0x1A2B3C4D);
+    // Interface Description Block of LINKTYPE_USB_LINUX_MMAPPED
+    let idb = u32_at(&bytes, 4) as usize;
+    assert_eq!(u32_at(&bytes, idb), 1);
+    assert_eq!(u16::from_le_bytes([bytes[idb + 8], bytes[idb + 9]]), 220);
+
+    let mut pos = idb + u32_at(&bytes, idb + 4) as usize;
+    let mut packets = Vec::new();
+    while pos < bytes.len() {
+        let len = u32_at(&bytes, pos + 4) as usize;
+        assert_eq!(u32_at(&bytes, pos), 6);
+        assert_eq!(u32_at(&bytes, pos + len - 4) as usize, len);
+        let captured = u32_at(&bytes, pos + 20) as usize;
+        packets.push(bytes[pos + 28..pos + 28 + captured].to_vec());
+        pos += len;
+    }
+    assert_eq!(pos, bytes.len());
+    // usbmon header precedes each report
+    assert!(packets.iter().all(|p| p.len() >= 64));
+    // UART data written to interface 1 and read back
+    let data = |p: &Vec<u8>| p[64..].to_vec();
+    assert!(packets.iter().any(|p| p[8] == b'S'
+        && p[10] == 0x04
+        && data(p).starts_with(&[0xF0, 3, b'a', b'b', b'c'])));
+    assert!(packets.iter().any(|p| p[8] == b'C'
+        && p[10] == 0x83
+        && data(p).starts_with(&[0xF0, 3, b'a', b'b', b'c'])));
+    // System Setting request with its setup packet
+    assert!(packets
+        .iter()
+        .any(|p| p[9] == 2 && p[40..42] == [0x21, 0x09] && data(p)[0] == 0xA1));
+}
+
+#[test]
+fn capture_line_fraction() {
+    // a short fraction is a fraction of a second, not a count of microseconds
+    let entry = Entry::from_line("1.5 0 > output d0 2002").unwrap().unwrap();
+    assert_eq!(entry.timestamp, Duration::from_micros(1_500_000));
+    assert!(entry.to_line().starts_with("1.500000 "));
+    assert_eq!(Entry::from_line(&entry.to_line()).unwrap(), Some(entry));
+
+    let entry = Entry::from_line("0.000042 0 < input d0 -")
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.timestamp, Duration::from_micros(42));
+
+    // more than microsecond precision or a missing fraction is rejected
+    assert!(Entry::from_line("0.0000001 0 > output d0 -").is_err());
+    assert!(Entry::from_line("0. 0 > output d0 -").is_err());
+    assert!(Entry::from_line("0.-5 0 > output d0 -").is_err());
+}
@@ -0,0 +1,219 @@
+use ft260hid::chip::{
+    ChipMode, Clock, ClockPolicy, FirmwareVersion, Gpio2Function, GpioAFunction, GpioGFunction,
+    InterruptTrigger, SuspendOutPolarity,
+};
+use ft260hid::device::{Device, Role};
+use ft260hid::io::gpio::{Group, Owner};
+use ft260hid::io::{i2c, uart};
+use ft260hid::sim::Simulator;
+use ft260hid::Ft260Error;
+
+use crate::common::sim_chip;
+
+#[test]
+fn chip_both() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+    assert_eq!(chip.mode(), ChipMode::Both);
+    assert!(chip.i2c().is_ok());
+    assert!(chip.uart().is_ok());
+    assert!(chip.device(1).is_some());
+    // interface 1 is missing
+    assert!(sim_chip(&sim, 1).is_err());
+}
+
+#[test]
+fn chip_pins_shared() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+    // UART on interface 1 owns pins against GPIO on interface 0
+    let uart = chip.uart().unwrap().init().unwrap();
+    let gpio = chip.gpio();
+    assert_eq!(gpio.owner(Group::Gpio_B_C_D_E_F_H), Some(Owner::Uart));
+    assert!(matches!(
+        gpio.enable_pin(Group::Gpio_B_C_D_E_F_H),
+        Err(Ft260Error::PinConflict {
+            group: Group::Gpio_B_C_D_E_F_H,
+            owner: Owner::Uart
+        })
+    ));
+    drop(uart);
+    assert!(gpio.enable_pin(Group::Gpio_B_C_D_E_F_H).is_ok());
+}
+
+#[test]
+fn chip_i2c_only() {
+    let sim = Simulator::new();
+    sim.set_chip_mode(ChipMode::I2c);
+    let chip = sim_chip(&sim, 1).unwrap();
+    assert_eq!(chip.mode(), ChipMode::I2c);
+    assert!(chip.i2c().is_ok());
+    match chip.uart() {
+        Err(Ft260Error::Unavailable { role, mode }) => {
+            assert_eq!(role, Role::Uart);
+            assert_eq!(mode, ChipMode::I2c);
+        }
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn chip_uart_only() {
+    let sim = Simulator::new();
+    sim.set_chip_mode(ChipMode::Uart);
+    sim.set_uart_loopback(true);
+    let chip = sim_chip(&sim, 1).unwrap();
+    assert_eq!(chip.mode(), ChipMode::Uart);
+    assert!(matches!(
+        chip.i2c(),
+        Err(Ft260Error::Unavailable {
+            role: Role::I2c,
+            mode: ChipMode::Uart
+        })
+    ));
+    let uart = chip.uart().unwrap().init().unwrap();
+    assert_eq!(uart.write(b"abc", 3).unwrap(), 3);
+    let mut buf = [0u8; 3];
+    assert_eq!(
+        uart.read(&mut buf, 3, uart::DURATION_WAIT_DEFAULT).unwrap(),
+        3
+    );
+    assert_eq!(&buf, b"abc");
+}
+
+#[test]
+fn chip_system_status() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    let st = dev.system_status().unwrap();
+    assert_eq!(st.chip_mode, ChipMode::Both);
+    assert_eq!(st.clock, Clock::Mhz48);
+    assert!(!st.suspended);
+    assert!(st.pwren_ready);
+    assert!(st.i2c_enabled);
+    assert_eq!(st.uart_mode, uart::Mode::NoFlowControl);
+    assert!(!st.hid_over_i2c);
+    assert_eq!(st.gpio2_function, Gpio2Function::SuspOut);
+    assert_eq!(st.gpio_a_function, GpioAFunction::TxActive);
+    assert_eq!(st.gpio_g_function, GpioGFunction::BcdDet);
+    assert_eq!(st.suspend_out_polarity, SuspendOutPolarity::High);
+    assert!(st.wakeup_interrupt_enabled);
+    assert_eq!(st.interrupt_trigger, InterruptTrigger::Rising);
+    assert_eq!(st.interrupt_duration, None);
+    assert!(!st.power_saving_enabled);
+
+    let gpio = dev.gpio();
+    for group in [
+        Group::Gpio_0_1,
+        Group::Gpio_2,
+        Group::Gpio_3,
+        Group::Gpio_A,
+        Group::Gpio_B_C_D_E_F_H,
+        Group::Gpio_G,
+    ] {
+        assert!(gpio.enable_pin(group).is_ok());
+    }
+    let st = dev.system_status().unwrap();
+    assert!(!st.i2c_enabled);
+    assert_eq!(st.uart_mode, uart::Mode::Off);
+    assert_eq!(st.gpio2_function, Gpio2Function::Gpio);
+    assert_eq!(st.gpio_a_function, GpioAFunction::Gpio);
+    assert_eq!(st.gpio_g_function, GpioGFunction::Gpio);
+    assert!(!st.wakeup_interrupt_enabled);
+}
+
+#[test]
+fn chip_clock_rate() {
+    for clock in Clock::ALL {
+        assert!(clock.supports_uart_baud(1200));
+        assert!(clock.supports_uart_baud(12_000_000));
+        assert!(!clock.supports_uart_baud(300));
+        assert!(clock.supports_i2c_kbps(60));
+        assert!(clock.supports_i2c_kbps(3400));
+        assert!(!clock.supports_i2c_kbps(10));
+    }
+}
+
+#[test]
+fn chip_clock_policy() {
+    let sim = Simulator::new();
+    let dev_i2c = Device::from_transport(sim.interface(0));
+    let dev_uart = Device::from_transport(sim.interface(1));
+    assert_eq!(dev_i2c.clock().unwrap(), Clock::Mhz48);
+
+    let (i2c, rate) = dev_i2c
+        .i2c()
+        .init_with_clock(400, ClockPolicy::Lowest)
+        .unwrap();
+    assert_eq!(rate.clock, Clock::Mhz12);
+    assert_eq!(rate.requested, 400);
+    assert_eq!(dev_uart.clock().unwrap(), Clock::Mhz12);
+    drop(i2c);
+    assert!(matches!(
+        dev_i2c.i2c().init_with_clock(5000, ClockPolicy::Lowest),
+        Err(Ft260Error::I2cError { .. })
+    ));
+    // the current clock is kept even if the speed is out of the range
+    assert!(dev_i2c.set_clock(Clock::Mhz24).is_ok());
+    let (i2c, rate) = dev_i2c
+        .i2c()
+        .init_with_clock(5000, ClockPolicy::Keep)
+        .unwrap();
+    assert_eq!(rate.clock, Clock::Mhz24);
+    drop(i2c);
+    assert!(dev_i2c.i2c().init(3400).is_ok());
+    assert_eq!(dev_i2c.clock().unwrap(), Clock::Mhz24);
+
+    let uart = dev_uart.uart().init().unwrap();
+    let mut cfg = uart::Config::default();
+    assert!(uart.set_config(&cfg).is_ok());
+    cfg.baud = 6_000_000;
+    assert!(uart.set_config(&cfg).is_ok());
+    cfg.baud = 20_000_000;
+    assert!(matches!(
+        uart.set_config_with_clock(&cfg, ClockPolicy::Lowest),
+        Err(Ft260Error::UartError { .. })
+    ));
+    cfg.baud = 6_000_000;
+    let rate = uart
+        .set_config_with_clock(&cfg, ClockPolicy::Lowest)
+        .unwrap();
+    assert_eq!(rate.clock, Clock::Mhz12);
+    assert_eq!(rate.requested, 6_000_000);
+    assert_eq!(uart.get_config().unwrap().baud, 6_000_000);
+
+    assert!(dev_i2c.set_clock(Clock::Mhz48).is_ok());
+    assert_eq!(dev_i2c.system_status().unwrap().clock, Clock::Mhz48);
+    assert!(dev_i2c.i2c().init(i2c::KBPS_DEFAULT).is_ok());
+}
+
+#[test]
+fn chip_info_firmware() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    let info = dev.chip_info().unwrap();
+    assert!(info.is_ft260());
+    assert_eq!(info.part_number, 0x0260);
+    assert_eq!(
+        info.firmware,
+        FirmwareVersion {
+            minor_version: 2,
+            revision: 0
+        }
+    );
+    assert_eq!(info.to_string(), "FT0260 firmware 2.0");
+
+    sim.set_firmware(FirmwareVersion {
+        minor_version: 2,
+        revision: 1,
+    });
+    let firmware = dev.chip_info().unwrap().firmware;
+    assert!(firmware > info.firmware);
+    assert!(
+        firmware
+            < FirmwareVersion {
+                minor_version: 3,
+                revision: 0
+            }
+    );
+}
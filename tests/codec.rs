@@ -0,0 +1,156 @@
+//!
+//! Tests of report codec, which need no device.
+//!
+use ft260hid::codec::gpio::{GpioPinNum, GpioReport};
+use ft260hid::codec::i2c::{self, I2cBusStatus, I2cCondition, I2cStatus};
+use ft260hid::codec::system::{self, ChipCode, ChipMode, Clock, InterruptStatus, SystemStatus};
+use ft260hid::codec::uart::{self, UartConfig};
+use ft260hid::codec::{Error, ReportId, Request, DATA_LENGTH_MAX, REPORT_LENGTH_MAX};
+
+#[test]
+fn codec_data_report_id() {
+    assert_eq!(i2c::report_id(0).unwrap(), ReportId::InOutI2cReport04);
+    assert_eq!(i2c::report_id(4).unwrap(), ReportId::InOutI2cReport04);
+    assert_eq!(i2c::report_id(5).unwrap(), ReportId::InOutI2cReport08);
+    assert_eq!(i2c::report_id(60).unwrap(), ReportId::InOutI2cReport3C);
+    assert_eq!(
+        i2c::report_id(61),
+        Err(Error::DataTooLong { len: 61, max: 60 })
+    );
+    assert_eq!(uart::report_id(9).unwrap(), ReportId::InOutUartReport0C);
+
+    assert_eq!(
+        i2c::capacity(ReportId::InOutI2cReport3C),
+        Some(DATA_LENGTH_MAX)
+    );
+    assert_eq!(i2c::capacity(ReportId::InOutI2cReportOverflow), None);
+    assert_eq!(i2c::capacity(ReportId::InOutUartReport04), None);
+    assert_eq!(uart::capacity(ReportId::InOutUartReport08), Some(8));
+}
+
+#[test]
+fn codec_i2c() {
+    let mut buf = [0xFFu8; REPORT_LENGTH_MAX];
+    let len = i2c::encode_write(&mut buf, 0x50, I2cCondition::Start, &[1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(len, 12);
+    assert_eq!(buf[..len], [0xD1, 0x50, 0x02, 5, 1, 2, 3, 4, 5, 0, 0, 0]);
+
+    let len = i2c::encode_read_request(&mut buf, 0x50, I2cCondition::StartAndStop, 0x102).unwrap();
+    assert_eq!(buf[..len], [0xC2, 0x50, 0x06, 0x02, 0x01]);
+
+    let mut short = [0u8; 8];
+    assert_eq!(
+        i2c::encode_write(&mut short, 0x50, I2cCondition::None, &[0; 8]),
+        Err(Error::BufferTooSmall {
+            needed: 12,
+            actual: 8
+        })
+    );
+
+    // input reports may be padded beyond the data
+    let report = [0xD1, 6, 1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0];
+    assert_eq!(i2c::decode_input(&report).unwrap(), &[1, 2, 3, 4, 5, 6]);
+    assert_eq!(
+        i2c::decode_input(&[0xD0, 5, 1, 2, 3, 4, 5]),
+        Err(Error::InvalidValue { value: 5 })
+    );
+    assert_eq!(
+        i2c::decode_input(&[0xF0, 1, 1]),
+        Err(Error::WrongReportId { report_id: 0xF0 })
+    );
+    assert_eq!(
+        i2c::decode_input(&[0xD0, 3, 1]),
+        Err(Error::ShortReport {
+            report_id: 0xD0,
+            len: 3,
+            needed: 5
+        })
+    );
+
+    let status = I2cStatus::decode(&[0xC0, 0x20, 100, 0, 0]).unwrap();
+    assert_eq!(status.bus, I2cBusStatus::ControllerIdle);
+    assert_eq!(status.kbps, 100);
+}
+
+#[test]
+fn codec_uart() {
+    let mut buf = [0u8; REPORT_LENGTH_MAX];
+    let len = uart::encode_write(&mut buf, b"hello").unwrap();
+    assert_eq!(buf[..len], [0xF1, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0]);
+    assert_eq!(uart::decode_input(&buf[..len]).unwrap(), b"hello");
+
+    let report = [0xE0, 4, 0x80, 0x25, 0, 0, 8, 0, 0, 0];
+    let config = UartConfig::decode(&report).unwrap();
+    assert_eq!(config.baud_rate, 9600);
+    assert_eq!(config.mode, uart::Mode::NoFlowControl);
+    let len = config.encode_request(&mut buf).unwrap();
+    assert_eq!(buf[..len], [0xA1, 0x41, 4, 0x80, 0x25, 0, 0, 8, 0, 0, 0]);
+
+    assert_eq!(
+        UartConfig::decode(&[0xE0, 9, 0x80, 0x25, 0, 0, 8, 0, 0, 0]),
+        Err(Error::InvalidValue { value: 9 })
+    );
+    assert_eq!(
+        uart::decode_dcd_ri_status(&[0xE2, 3]).unwrap(),
+        uart::UartDcdRiStatus::all()
+    );
+}
+
+#[test]
+fn codec_system() {
+    let code = ChipCode::decode(&[0xA0, 0x02, 0x60, 0x02, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+    assert_eq!(code.part_number, 0x0260);
+    assert_eq!(code.minor_version, 2);
+
+    let mut report = [0u8; SystemStatus::LENGTH];
+    report[0] = 0xA1;
+    report[1] = 3; // DCNF0 and DCNF1
+    report[2] = 2; // 48MHz
+    report[13] = 0x09; // high level for 5ms
+    let status = SystemStatus::decode(&report).unwrap();
+    assert_eq!(status.chip_mode, ChipMode::Both);
+    assert_eq!(status.clock, Clock::Mhz48);
+    assert_eq!(status.interrupt_trigger, system::InterruptTrigger::High);
+    assert_eq!(
+        status.interrupt_duration,
+        Some(system::InterruptDuration::Ms5)
+    );
+    report[13] = 0x0A; // falling edge with duration bits set
+    let status = SystemStatus::decode(&report).unwrap();
+    assert_eq!(status.interrupt_trigger, system::InterruptTrigger::Falling);
+    assert_eq!(status.interrupt_duration, None);
+    assert!(matches!(
+        SystemStatus::decode(&report[..14]),
+        Err(Error::ShortReport { .. })
+    ));
+    // unknown value of another field fails only the whole status
+    report[9] = 0xFF;
+    assert!(SystemStatus::decode(&report).is_err());
+    assert_eq!(SystemStatus::decode_clock(&report).unwrap(), Clock::Mhz48);
+
+    let mut buf = [0u8; 4];
+    let len =
+        system::encode_request(&mut buf, Request::SetI2cClockSpeed, &400u16.to_le_bytes()).unwrap();
+    assert_eq!(buf[..len], [0xA1, 0x22, 0x90, 0x01]);
+
+    let status = InterruptStatus::decode(&[0xB1, 1, 2]).unwrap();
+    assert!(status.intrin);
+    assert_eq!(status.uart, system::UartInterruptStatus::Ri);
+}
+
+#[test]
+fn codec_gpio() {
+    let report = GpioReport {
+        val: GpioPinNum::GPIO_2,
+        dir: GpioPinNum::GPIO_2 | GpioPinNum::GPIO_3 | GpioPinNum::GPIO_H,
+    };
+    let mut buf = [0u8; GpioReport::LENGTH];
+    assert_eq!(report.encode(&mut buf).unwrap(), GpioReport::LENGTH);
+    // GPIO 0-5 and GPIO A-H are in separate bytes
+    assert_eq!(buf, [0xB0, 0x04, 0x0C, 0x00, 0x80]);
+    assert_eq!(GpioReport::decode(&buf).unwrap(), report);
+
+    // bits 6-7 of GPIO 0-5 are not pins
+    let report = GpioReport::decode(&[0xB0, 0xE0, 0x00, 0x01, 0x00]).unwrap();
+    assert_eq!(report.val, GpioPinNum::GPIO_5 | GpioPinNum::GPIO_A);
+}
@@ -0,0 +1,116 @@
+//!
+//! Fixtures shared by tests.
+//!
+
+use std::env::VarError;
+use std::thread;
+use std::time::Duration;
+
+use ft260hid::chip::Chip;
+use ft260hid::device::{self, Device};
+use ft260hid::io::gpio::Pin;
+use ft260hid::sim::{Eeprom, SimInterface, Simulator};
+use ft260hid::transport::Transport;
+use ft260hid::Ft260Result;
+
+/// Address of EEPROM (AT24C02D) mounted on UMFT260EV1A board
+pub const EEPROM_ADDRESS: u8 = 0x50;
+/// Page size of the EEPROM
+pub const EEPROM_PAGE_SIZE: usize = 8;
+
+/// Chip made of the first `count` interfaces of `sim`
+pub fn sim_chip(sim: &Simulator, count: usize) -> Ft260Result<Chip> {
+    let devices = (0..count)
+        .map(|n| Device::from_transport(sim.interface(n)))
+        .collect();
+    Chip::from_devices(devices)
+}
+
+/// Environment variable choosing the backend of `Board`, `sim` (default) or `hardware`
+pub const BACKEND_VAR: &str = "FT260_TEST_BACKEND";
+
+/// Interfaces of UMFT260EV1A board set up as described in `lib.rs`,
+/// or of `Simulator` set up in the same way, chosen by `BACKEND_VAR`
+pub struct Board {
+    /// Interface 0 for I2C
+    pub i2c: Device,
+    /// Interface 1 for UART
+    pub uart: Device,
+    /// Simulator running instead of the board
+    pub sim: Option<Simulator>,
+}
+
+impl Board {
+    /// Open the backend chosen by `BACKEND_VAR`.
+    /// Panics if the board is chosen but not connected, not to pass the tests on the simulator.
+    pub fn open() -> Self {
+        match std::env::var(BACKEND_VAR).as_deref() {
+            Ok("hardware") => Self::open_hardware(),
+            Ok("sim") | Err(VarError::NotPresent) => Self::open_sim(),
+            other => panic!(
+                "{} should be `sim` or `hardware`, not {:?}",
+                BACKEND_VAR, other
+            ),
+        }
+    }
+
+    fn open_hardware() -> Self {
+        eprintln!("testing on UMFT260EV1A board");
+        let (Some(i2c), Some(uart)) = (device::open(0), device::open(1)) else {
+            panic!("UMFT260EV1A board is not connected");
+        };
+        Self {
+            i2c,
+            uart,
+            sim: None,
+        }
+    }
+
+    fn open_sim() -> Self {
+        eprintln!(
+            "testing on simulator, set {}=hardware to test on the board",
+            BACKEND_VAR
+        );
+        let sim = Simulator::new();
+        sim.attach_i2c(EEPROM_ADDRESS, Eeprom::at24c02());
+        // TXD - RXD and RTS - CTS short-circuited
+        sim.set_uart_loopback(true);
+        sim.wire(Pin::GpioD, Pin::GpioC);
+        sim.wire(Pin::GpioB, Pin::GpioE);
+        Self {
+            i2c: Device::from_transport(sim.interface(0)),
+            uart: Device::from_transport(sim.interface(1)),
+            sim: Some(sim),
+        }
+    }
+}
+
+/// Interface of simulator taking time to transfer feature reports as USB does,
+/// which lets other threads transfer in between
+pub struct SlowInterface(pub SimInterface);
+
+impl Transport for SlowInterface {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        self.0.read_timeout(buf, timeout)
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        self.0.write(data)
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        let res = self.0.get_feature_report(buf);
+        thread::sleep(Duration::from_millis(1));
+        res
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        let res = self.0.send_feature_report(data);
+        thread::sleep(Duration::from_millis(1));
+        res
+    }
+
+    fn is_present(&self) -> bool {
+        self.0.is_present()
+    }
+}
@@ -0,0 +1,91 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ft260hid::device::{Device, FifoStats, OverflowPolicy, ReportClass};
+use ft260hid::io::{uart, Initialized};
+use ft260hid::sim::Simulator;
+
+const WAIT: Duration = Duration::from_millis(500);
+
+/// Wait until the reading thread takes all input reports out of the simulator
+fn wait_stats(dev: &Device, cond: impl Fn(&FifoStats) -> bool) -> FifoStats {
+    let start = Instant::now();
+    loop {
+        let stats = dev.fifo_stats(ReportClass::Uart);
+        if cond(&stats) || start.elapsed() > Duration::from_secs(5) {
+            return stats;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn uart_device(sim: &Simulator, policy: OverflowPolicy, capacity: usize) -> Device {
+    let dev = Device::from_transport(sim.interface(1));
+    dev.set_fifo_limit(ReportClass::Uart, capacity, policy);
+    dev
+}
+
+fn read_all(uart: &uart::Uart<Initialized>) -> Vec<u8> {
+    let mut buf = [0u8; 64];
+    let len = uart.size_to_read();
+    let sz = uart.read(&mut buf, len, WAIT).unwrap();
+    buf[..sz].to_vec()
+}
+
+#[test]
+fn fifo_drop_oldest() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = uart_device(&sim, OverflowPolicy::DropOldest, 2);
+    let uart = dev.uart().init().unwrap();
+
+    for chunk in [b"one", b"two", b"six"] {
+        peer.write(chunk);
+    }
+    let stats = wait_stats(&dev, |s| s.received == 3);
+    assert_eq!(stats.len, 2);
+    assert_eq!(stats.dropped, 1);
+    assert_eq!(stats.high_water, 2);
+    assert_eq!(read_all(&uart), b"twosix");
+
+    dev.reset_fifo_stats(ReportClass::Uart);
+    let stats = dev.fifo_stats(ReportClass::Uart);
+    assert_eq!((stats.received, stats.dropped, stats.high_water), (0, 0, 0));
+}
+
+#[test]
+fn fifo_drop_newest() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = uart_device(&sim, OverflowPolicy::DropNewest, 2);
+    let uart = dev.uart().init().unwrap();
+
+    for chunk in [b"one", b"two", b"six"] {
+        peer.write(chunk);
+    }
+    let stats = wait_stats(&dev, |s| s.received == 3);
+    assert_eq!(stats.dropped, 1);
+    assert_eq!(read_all(&uart), b"onetwo");
+}
+
+#[test]
+fn fifo_backpressure() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = uart_device(&sim, OverflowPolicy::Backpressure, 1);
+    let uart = dev.uart().init().unwrap();
+
+    for chunk in [b"one", b"two", b"six"] {
+        peer.write(chunk);
+    }
+    let stats = wait_stats(&dev, |s| s.received == 1);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(dev.fifo_stats(ReportClass::Uart), stats);
+    assert_eq!(stats.len, 1);
+
+    let mut buf = [0u8; 9];
+    assert_eq!(uart.read(&mut buf, 9, WAIT).unwrap(), 9);
+    assert_eq!(&buf, b"onetwosix");
+    let stats = dev.fifo_stats(ReportClass::Uart);
+    assert_eq!((stats.received, stats.dropped), (3, 0));
+}
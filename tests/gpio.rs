@@ -1,15 +1,19 @@
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use ft260hid::device;
+use ft260hid::device::Device;
 use ft260hid::io::gpio::{Dir, Group, Pin, Val};
+use ft260hid::sim::Simulator;
 use serial_test::serial;
 
+use crate::common::{Board, SlowInterface};
+
 #[test]
 #[serial]
 fn read_value() {
-    let dev = device::open(0).unwrap();
-    let gpio = dev.gpio();
+    let board = Board::open();
+    let gpio = board.i2c.gpio();
     assert!(gpio.enable_pin(Group::Gpio_B_C_D_E_F_H).is_ok());
     assert!(gpio.set_dir(Pin::GpioB, Dir::Out).is_ok()); // RTS / GPIOB
     assert!(gpio.set_dir(Pin::GpioE, Dir::In).is_ok()); // CTS / GPIOE
@@ -54,10 +58,48 @@ fn read_value() {
     assert!(gpio.set_dir(Pin::Gpio1, Dir::In).is_ok()); // SDA / GPIO1
     assert!(gpio.set_pull_up(Pin::Gpio0).is_ok());
     thread::sleep(delay);
+    assert_eq!(gpio.read(Pin::Gpio0).unwrap(), Val::High);
     assert!(gpio.set_pull_up(Pin::Gpio1).is_ok());
     thread::sleep(delay);
     assert!(gpio.set_pull_down(Pin::Gpio0).is_ok());
     thread::sleep(delay);
+    if board.sim.is_some() {
+        assert_eq!(gpio.read(Pin::Gpio0).unwrap(), Val::Low);
+    } else {
+        // pull-ups of I2C bus on the board override the internal pull-down
+        assert_eq!(gpio.read(Pin::Gpio0).unwrap(), Val::High);
+    }
     assert!(gpio.set_pull_down(Pin::Gpio1).is_ok());
     thread::sleep(delay);
 }
+
+#[test]
+fn concurrent_pins() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(SlowInterface(sim.interface(0)));
+    let gpio = Arc::new(dev.gpio());
+    assert!(gpio.enable_pin(Group::Gpio_B_C_D_E_F_H).is_ok());
+
+    // each write of a pin reads and writes the report holding all pins
+    let workers: Vec<_> = [Pin::GpioB, Pin::GpioC, Pin::GpioD, Pin::GpioE]
+        .into_iter()
+        .map(|pin| {
+            let gpio = gpio.clone();
+            thread::spawn(move || {
+                for i in 0..20 {
+                    let (dir, val) = if i % 2 == 0 {
+                        (Dir::Out, Val::High)
+                    } else {
+                        (Dir::In, Val::Low)
+                    };
+                    assert!(gpio.set_dir(pin, dir).is_ok());
+                    assert!(gpio.write(pin, val).is_ok());
+                    assert_eq!(gpio.read(pin).unwrap(), val);
+                }
+            })
+        })
+        .collect();
+    for w in workers {
+        w.join().unwrap();
+    }
+}
@@ -0,0 +1,312 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ft260hid::device::{Device, DeviceDescriptor, Role};
+use ft260hid::hotplug::{Event, Watcher};
+use ft260hid::io::gpio::{Dir, Group, Pin, Val};
+use ft260hid::io::uart;
+use ft260hid::sim::{SimInterface, Simulator};
+use ft260hid::transport::{Reconnect, Transport};
+use ft260hid::{Ft260Error, Ft260Result};
+
+const INTERVAL: Duration = Duration::from_millis(10);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn descriptor(path: &str, interface: i32) -> DeviceDescriptor {
+    DeviceDescriptor {
+        path: path.to_string(),
+        vendor_id: 0x0403,
+        product_id: 0x6030,
+        serial_number: Some("SIM0001".to_string()),
+        manufacturer: None,
+        product: None,
+        interface,
+        release_number: 0x0100,
+        role: if interface == 1 {
+            Role::Uart
+        } else {
+            Role::I2c
+        },
+    }
+}
+
+fn reconnect(sim: &Simulator, interface: usize) -> Reconnect {
+    let sim = sim.clone();
+    Reconnect::new(
+        move || -> Ft260Result<SimInterface> {
+            if sim.is_plugged() {
+                Ok(sim.interface(interface))
+            } else {
+                Err(Ft260Error::HidError {
+                    message: "not found".to_string(),
+                })
+            }
+        },
+        TIMEOUT,
+    )
+}
+
+/// Interface of simulator failing as hidraw backend of hidapi does,
+/// which reports unplug with the same error as any other failure
+struct HidrawInterface(SimInterface);
+
+impl HidrawInterface {
+    fn map<T>(res: Ft260Result<T>) -> Ft260Result<T> {
+        res.map_err(|e| match e {
+            Ft260Error::Disconnected { .. } => Ft260Error::HidError {
+                message: "hidapi error: No such device".to_string(),
+            },
+            e => e,
+        })
+    }
+}
+
+impl Transport for HidrawInterface {
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        Self::map(self.0.read_timeout(buf, timeout))
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        Self::map(self.0.write(data))
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        Self::map(self.0.get_feature_report(buf))
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        Self::map(self.0.send_feature_report(data))
+    }
+
+    fn is_present(&self) -> bool {
+        self.0.is_present()
+    }
+}
+
+/// Unplug the simulator and plug it again after a while
+fn glitch(sim: &Simulator) -> thread::JoinHandle<()> {
+    sim.unplug();
+    let sim = sim.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        sim.plug();
+    })
+}
+
+#[test]
+fn hotplug_events() {
+    let usb = Arc::new(Mutex::new(vec![descriptor("a0", 0), descriptor("a1", 1)]));
+    let (tx, rx) = std::sync::mpsc::channel();
+    let watcher = Watcher::with_enumerator(
+        INTERVAL,
+        {
+            let usb = usb.clone();
+            move || Ok(usb.lock().unwrap().clone())
+        },
+        move |event| tx.send(event).unwrap(),
+    );
+    assert_eq!(rx.recv().unwrap(), Event::Arrived(descriptor("a0", 0)));
+    assert_eq!(rx.recv().unwrap(), Event::Arrived(descriptor("a1", 1)));
+
+    usb.lock().unwrap().clear();
+    assert_eq!(rx.recv().unwrap(), Event::Removed(descriptor("a0", 0)));
+    assert_eq!(rx.recv().unwrap(), Event::Removed(descriptor("a1", 1)));
+
+    usb.lock().unwrap().push(descriptor("b0", 0));
+    assert_eq!(rx.recv().unwrap(), Event::Arrived(descriptor("b0", 0)));
+
+    // the callback is dropped with the thread
+    drop(watcher);
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn hotplug_disconnected() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    assert!(dev.is_connected());
+    sim.unplug();
+    thread::sleep(Duration::from_millis(50));
+    assert!(!dev.is_connected());
+    assert!(matches!(
+        dev.system_status(),
+        Err(Ft260Error::Disconnected { .. })
+    ));
+    // the old interface is dead even after plugged again
+    sim.plug();
+    assert!(dev.system_status().is_err());
+}
+
+#[test]
+fn hotplug_reconnect_i2c_gpio() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(reconnect(&sim, 0));
+    let i2c = dev.i2c().init(400).unwrap();
+    let gpio = dev.gpio();
+    assert!(gpio.enable_pin(Group::Gpio_A).is_ok());
+    assert!(gpio.set_dir(Pin::GpioA, Dir::Out).is_ok());
+    assert!(gpio.write(Pin::GpioA, Val::High).is_ok());
+
+    let plug = glitch(&sim);
+    assert_eq!(gpio.read(Pin::GpioA).unwrap(), Val::High);
+    plug.join().unwrap();
+
+    assert_eq!(sim.last_request(0x22), Some(vec![144, 1]));
+    let st = dev.system_status().unwrap();
+    assert_eq!(st.gpio_a_function, ft260hid::chip::GpioAFunction::Gpio);
+    assert!(st.i2c_enabled);
+    assert_eq!(i2c.is_idle(), Some(true));
+}
+
+#[test]
+fn hotplug_reconnect_uart() {
+    let sim = Simulator::new();
+    sim.set_uart_loopback(true);
+    let dev = Device::from_transport(reconnect(&sim, 1));
+    let uart = dev.uart().init().unwrap();
+    let cfg = uart::Config {
+        mode: uart::Mode::RtsCts,
+        baud: 57600,
+        data_bits: uart::DataBits::Seven,
+        stop_bit: uart::StopBit::Two,
+        parity: uart::Parity::Odd,
+        breaking: uart::Breaking::NoBreak,
+    };
+    assert!(uart.set_config(&cfg).is_ok());
+    assert!(uart
+        .set_config(&uart::Config { baud: 19200, ..cfg })
+        .is_ok());
+
+    glitch(&sim).join().unwrap();
+    assert_eq!(
+        uart.get_config().unwrap(),
+        uart::Config { baud: 19200, ..cfg }
+    );
+    assert_eq!(uart.write(b"again", 5).unwrap(), 5);
+    let mut buf = [0u8; 5];
+    assert_eq!(
+        uart.read(&mut buf, 5, uart::DURATION_WAIT_DEFAULT).unwrap(),
+        5
+    );
+    assert_eq!(&buf, b"again");
+    // I2C speed was never set on this chip
+    assert!(sim.last_request(0x22).is_none());
+}
+
+#[test]
+fn hotplug_reconnect_errors() {
+    let sim = Simulator::new();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let transport = Reconnect::new(
+        {
+            let sim = sim.clone();
+            let connections = connections.clone();
+            move || -> Ft260Result<SimInterface> {
+                connections.fetch_add(1, Ordering::Relaxed);
+                Ok(sim.interface(0))
+            }
+        },
+        TIMEOUT,
+    );
+    assert!(transport.send_feature_report(&[0xA1, 0x22, 144, 1]).is_ok());
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+    // other errors don't reconnect
+    assert!(matches!(
+        transport.send_feature_report(&[0x55]),
+        Err(Ft260Error::HidError { .. })
+    ));
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+    // a write failed by disconnection is not sent again after reconnection
+    sim.unplug();
+    sim.plug();
+    assert!(matches!(
+        transport.send_feature_report(&[0xA1, 0x22, 100, 0]),
+        Err(Ft260Error::Disconnected { .. })
+    ));
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+    assert_eq!(sim.last_request(0x22), Some(vec![144, 1]));
+    assert!(transport.send_feature_report(&[0xA1, 0x22, 100, 0]).is_ok());
+    assert_eq!(sim.last_request(0x22), Some(vec![100, 0]));
+
+    // while a read is retried
+    sim.unplug();
+    sim.plug();
+    let mut buf = [0u8; 64];
+    buf[0] = 0xA1;
+    assert!(transport.get_feature_report(&mut buf).is_ok());
+    assert_eq!(connections.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn hotplug_reconnect_hid_error() {
+    let sim = Simulator::new();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let transport = Reconnect::new(
+        {
+            let sim = sim.clone();
+            let connections = connections.clone();
+            move || -> Ft260Result<HidrawInterface> {
+                connections.fetch_add(1, Ordering::Relaxed);
+                Ok(HidrawInterface(sim.interface(0)))
+            }
+        },
+        TIMEOUT,
+    );
+    assert!(transport.send_feature_report(&[0xA1, 0x22, 144, 1]).is_ok());
+
+    // an error of the interface still present doesn't reconnect
+    assert!(matches!(
+        transport.send_feature_report(&[0x55]),
+        Err(Ft260Error::HidError { .. })
+    ));
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+    // the same error after unplug does
+    sim.unplug();
+    sim.plug();
+    assert!(matches!(
+        transport.send_feature_report(&[0xA1, 0x22, 100, 0]),
+        Err(Ft260Error::Disconnected { .. })
+    ));
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+    assert_eq!(sim.last_request(0x22), Some(vec![144, 1]));
+
+    sim.unplug();
+    sim.plug();
+    let mut buf = [0u8; 64];
+    buf[0] = 0xA1;
+    assert!(transport.get_feature_report(&mut buf).is_ok());
+    assert_eq!(connections.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn hotplug_reconnect_drop() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(Reconnect::new(
+        {
+            let sim = sim.clone();
+            move || -> Ft260Result<SimInterface> {
+                if sim.is_plugged() {
+                    Ok(sim.interface(0))
+                } else {
+                    Err(Ft260Error::HidError {
+                        message: "not found".to_string(),
+                    })
+                }
+            }
+        },
+        Duration::from_secs(60),
+    ));
+    assert!(dev.system_status().is_ok());
+
+    // the reading thread is connecting again when the device is dropped
+    sim.unplug();
+    thread::sleep(Duration::from_millis(300));
+    let start = Instant::now();
+    drop(dev);
+    assert!(start.elapsed() < TIMEOUT);
+}
@@ -1,16 +1,14 @@
 use std::thread;
 use std::time::Duration;
 
-use ft260hid::device;
-use ft260hid::io::i2c;
+use ft260hid::io::{i2c, Initialized};
 
 use rand::prelude::*;
 use serial_test::serial;
 
-const EEPROM_ADDRESS: u8 = 0x50;
-const EEPROM_PAGE_SIZE: usize = 8;
+use crate::common::{Board, EEPROM_ADDRESS, EEPROM_PAGE_SIZE};
 
-fn wait_in_busy(i2c: &i2c::I2c) {
+fn wait_in_busy(i2c: &i2c::I2c<Initialized>) {
     loop {
         match i2c.is_idle() {
             Some(true) => {
@@ -24,7 +22,7 @@ fn wait_in_busy(i2c: &i2c::I2c) {
     }
 }
 
-fn wait_write(i2c: &i2c::I2c) {
+fn wait_write(i2c: &i2c::I2c<Initialized>) {
     wait_in_busy(i2c);
     // write cycle time 5ms typ.
     thread::sleep(Duration::from_millis(5));
@@ -36,13 +34,13 @@ fn test_i2c_basic() {
     let mut rand = [0u8; EEPROM_PAGE_SIZE];
     thread_rng().fill(&mut rand);
 
-    let dev = device::open(0).unwrap();
-    let mut i2c = dev.i2c();
+    let board = Board::open();
+    let dev = &board.i2c;
 
     // Test I2C communication with "AT24C02D" mounted on "UMFT260EV1A" board
     // 7bit device address : 0b1010000 = 0x50
 
-    assert!(i2c.init(i2c::KBPS_DEFAULT).is_ok());
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
     wait_in_busy(&i2c);
     let data_write = [rand[0], rand[1]]; // [random address, random value]
     assert_eq!(
@@ -142,11 +140,11 @@ fn test_i2c_basic() {
 #[test]
 #[serial]
 fn test_i2c_write_read() {
-    let dev = device::open(0).unwrap();
-    let mut i2c = dev.i2c();
-    assert!(i2c.init(i2c::KBPS_DEFAULT).is_ok());
+    let board = Board::open();
+    let dev = &board.i2c;
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
 
-    for i in 0..10 {
+    for _ in 0..10 {
         let mut data = [0u8; EEPROM_PAGE_SIZE];
         thread_rng().fill(&mut data);
         let addr = [random::<u8>() & 0xF8u8];
@@ -187,10 +185,10 @@ fn test_i2c_long_data() {
     let kbps_list = [400u16, 100u16];
     let len_list = [256usize, 128usize, 64usize, 32usize, 16usize];
 
-    let dev = device::open(0).unwrap();
-    let mut i2c = dev.i2c();
+    let board = Board::open();
+    let dev = &board.i2c;
     for kbps in kbps_list {
-        assert!(i2c.init(kbps).is_ok());
+        let i2c = dev.i2c().init(kbps).unwrap();
         wait_in_busy(&i2c);
         for len in len_list {
             assert_eq!(
@@ -0,0 +1,170 @@
+//!
+//! Tests of interrupts on INTRIN, running on `sim::Simulator`.
+//!
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Group, Owner};
+use ft260hid::io::interrupt::{Event, InterruptDuration, InterruptTrigger};
+use ft260hid::power::RiWakeup;
+use ft260hid::sim::Simulator;
+use ft260hid::Ft260Error;
+
+use crate::common::sim_chip;
+
+const WAIT: Duration = Duration::from_secs(1);
+const NO_WAIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn interrupt_trigger() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+    let int = chip.interrupt().unwrap();
+    let int = int
+        .init(InterruptTrigger::Rising, InterruptDuration::Ms1)
+        .unwrap();
+    assert_eq!(int.trigger().unwrap(), (InterruptTrigger::Rising, None));
+    assert_eq!(chip.gpio().owner(Group::Gpio_3), Some(Owner::Interrupt));
+
+    sim.set_intrin(true);
+    assert_eq!(
+        int.wait(WAIT),
+        Some(Event {
+            dcd: false,
+            ri: false
+        })
+    );
+    sim.set_intrin(false);
+    assert_eq!(int.wait(NO_WAIT), None);
+
+    assert!(int
+        .set_trigger(InterruptTrigger::Low, InterruptDuration::Ms30)
+        .is_ok());
+    assert_eq!(
+        int.trigger().unwrap(),
+        (InterruptTrigger::Low, Some(InterruptDuration::Ms30))
+    );
+    sim.set_intrin(true);
+    assert_eq!(int.try_wait(), None);
+    sim.set_intrin(false);
+    assert!(int.wait(WAIT).is_some());
+    assert_eq!(int.try_wait(), None);
+
+    // the duration is kept for an edge, but not reported
+    assert!(int
+        .set_trigger(InterruptTrigger::Falling, InterruptDuration::Ms5)
+        .is_ok());
+    assert_eq!(sim.last_request(0x0A), Some(vec![0x02, 0x08]));
+    assert_eq!(int.trigger().unwrap(), (InterruptTrigger::Falling, None));
+
+    // GPIO3 is released and switched back to GPIO when dropped
+    drop(int);
+    assert_eq!(chip.gpio().owner(Group::Gpio_3), None);
+    let st = chip.device(0).unwrap().system_status().unwrap();
+    assert!(!st.wakeup_interrupt_enabled);
+}
+
+#[test]
+fn interrupt_listener() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+    let int = chip
+        .interrupt()
+        .unwrap()
+        .init(InterruptTrigger::Falling, InterruptDuration::Ms5)
+        .unwrap();
+
+    let (listener, rx) = int.channel();
+    for _ in 0..3 {
+        sim.set_intrin(true);
+        sim.set_intrin(false);
+    }
+    for _ in 0..3 {
+        assert!(rx.recv_timeout(WAIT).is_ok());
+    }
+    assert!(rx.recv_timeout(NO_WAIT).is_err());
+    drop(listener);
+    assert!(rx.recv_timeout(NO_WAIT).is_err());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let listener = int.on_event({
+        let events = events.clone();
+        move |e| events.lock().unwrap().push(e)
+    });
+    assert!(listener.is_running());
+    sim.set_intrin(true);
+    sim.set_intrin(false);
+    let start = std::time::Instant::now();
+    while events.lock().unwrap().is_empty() && start.elapsed() < WAIT {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(events.lock().unwrap().len(), 1);
+    drop(listener);
+
+    // events after the listener stopped are left for `wait`
+    sim.set_intrin(true);
+    sim.set_intrin(false);
+    assert!(int.wait(WAIT).is_some());
+    assert_eq!(events.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn interrupt_pins() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+
+    // GPIO3 used as GPIO or for wakeup
+    let gpio = chip.gpio();
+    assert!(gpio.enable_pin(Group::Gpio_3).is_ok());
+    assert!(matches!(
+        chip.interrupt()
+            .unwrap()
+            .init(InterruptTrigger::Rising, InterruptDuration::Ms1),
+        Err(Ft260Error::PinConflict {
+            owner: Owner::Gpio,
+            ..
+        })
+    ));
+    assert!(gpio.disable_pin(Group::Gpio_3).is_ok());
+    let power = chip.power();
+    assert!(power.enable_wakeup_pin().is_ok());
+    assert!(chip
+        .interrupt()
+        .unwrap()
+        .init(InterruptTrigger::Rising, InterruptDuration::Ms1)
+        .is_err());
+    assert!(power.disable_wakeup_pin().is_ok());
+
+    // RI wakeup doesn't use GPIO3
+    assert!(power.enable_ri_wakeup(RiWakeup::FallingEdge).is_ok());
+    let int = chip
+        .interrupt()
+        .unwrap()
+        .init(InterruptTrigger::Rising, InterruptDuration::Ms1)
+        .unwrap();
+    sim.set_intrin(true);
+    assert!(int.wait(WAIT).is_some());
+
+    // interrupts are reported on UART interface
+    sim.set_chip_mode(ft260hid::chip::ChipMode::I2c);
+    let chip = sim_chip(&sim, 1).unwrap();
+    assert!(matches!(
+        chip.interrupt(),
+        Err(Ft260Error::Unavailable { .. })
+    ));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn interrupt_async() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(1));
+    let int = dev
+        .interrupt()
+        .init(InterruptTrigger::High, InterruptDuration::Ms1)
+        .unwrap();
+    assert_eq!(int.wait_async(NO_WAIT).await, None);
+    sim.set_intrin(true);
+    assert!(int.wait_async(WAIT).await.is_some());
+}
@@ -31,7 +31,27 @@
 //! UART ports can be used also for testing GPIO functions.  
 //! To add, short-circuit `IO2` - `IO3`, or `JP6.9` to `JP.8`.  
 //!
+//! ## Without the board
+//!
+//! Tests of I2C, UART and GPIO run on `sim::Simulator` set up in the same way by default,
+//! and on the board with `FT260_TEST_BACKEND=hardware`, while the ones in `open.rs` always need the board.
+//!
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod capture;
+pub mod chip;
+pub mod codec;
+pub mod common;
+pub mod fifo;
 pub mod gpio;
+pub mod hotplug;
 pub mod i2c;
+pub mod interrupt;
 pub mod open;
+pub mod power;
+pub mod raw;
+pub mod replay;
+pub mod sim;
+pub mod trace;
+pub mod transport;
 pub mod uart;
@@ -1,6 +1,15 @@
-use ft260hid::device;
+use ft260hid::chip::{Chip, ChipMode};
+use ft260hid::device::{self, OpenOptions};
+use ft260hid::{Ft260Error, OpenErrorKind};
 use serial_test::serial;
 
+fn open_error_kind(opt: &OpenOptions) -> OpenErrorKind {
+    match opt.open() {
+        Err(Ft260Error::OpenError { kind, message: _ }) => kind,
+        _ => panic!(),
+    }
+}
+
 #[test]
 #[serial]
 fn open_hid() {
@@ -11,3 +20,80 @@ fn open_hid() {
     assert!(device::open(1).is_some());
     assert!(device::open(2).is_none());
 }
+
+#[test]
+#[serial]
+fn list_hid() {
+    let list = device::list().unwrap();
+    assert_eq!(list.len(), 2);
+    for desc in list {
+        assert_eq!((desc.vendor_id, desc.product_id), (0x0403, 0x6030));
+        match desc.interface {
+            0 => assert_eq!(desc.role, device::Role::I2c),
+            1 => assert_eq!(desc.role, device::Role::Uart),
+            _ => panic!(),
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn open_options() {
+    let list = device::list().unwrap();
+    let desc = list.iter().find(|d| d.interface == 1).unwrap();
+    let serial = desc.serial_number.clone().unwrap();
+
+    let dev = OpenOptions::new()
+        .serial_number(&serial)
+        .interface(1)
+        .open();
+    assert!(dev.is_ok());
+    // same interface can't be opened twice
+    assert_eq!(
+        open_error_kind(&OpenOptions::new().path(&desc.path)),
+        OpenErrorKind::AlreadyOpen
+    );
+    drop(dev);
+    assert!(OpenOptions::new().path(&desc.path).open().is_ok());
+
+    assert_eq!(
+        open_error_kind(&OpenOptions::new().path(&desc.path).interface(0)),
+        OpenErrorKind::WrongInterface
+    );
+    assert_eq!(
+        open_error_kind(&OpenOptions::new().serial_number(&serial).index(2)),
+        OpenErrorKind::NotFound
+    );
+}
+
+#[test]
+#[serial]
+fn open_options_not_found() {
+    assert_eq!(
+        open_error_kind(&OpenOptions::new().interface(2)),
+        OpenErrorKind::WrongInterface
+    );
+    assert_eq!(
+        open_error_kind(&OpenOptions::new().vid_pid(0x0403, 0x0000)),
+        OpenErrorKind::NotFound
+    );
+}
+
+#[test]
+#[serial]
+fn open_chip() {
+    let chip = Chip::open(&OpenOptions::new()).unwrap();
+    // DCNF0=1 and DCNF1=1 on UMFT260EV1A for the tests
+    assert_eq!(chip.mode(), ChipMode::Both);
+    assert!(chip.i2c().is_ok());
+    assert!(chip.uart().is_ok());
+}
+
+#[test]
+#[serial]
+fn open_chip_info() {
+    let dev = device::open(0).unwrap();
+    let info = dev.chip_info().unwrap();
+    assert!(info.is_ft260());
+    assert_eq!(dev.system_status().unwrap().chip_mode, ChipMode::Both);
+}
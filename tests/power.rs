@@ -0,0 +1,146 @@
+//!
+//! Tests of USB suspend behaviour, running on `sim::Simulator`.
+//!
+use ft260hid::chip::SuspendOutPolarity;
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Group, Owner, Pin, Val};
+use ft260hid::power::{RiWakeup, SuspendLevels, WakeupConfig};
+use ft260hid::sim::Simulator;
+use ft260hid::Ft260Error;
+
+use crate::common::sim_chip;
+
+#[test]
+fn power_status() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    let power = dev.power();
+    let st = power.status().unwrap();
+    assert!(!st.suspended);
+    assert!(st.pwren_ready);
+    assert!(!st.power_saving_enabled);
+    assert_eq!(st.suspend_out_polarity, SuspendOutPolarity::High);
+
+    sim.set_suspended(true);
+    assert!(power.is_suspended().unwrap());
+    assert!(!power.is_pwren_ready().unwrap());
+    sim.set_suspended(false);
+    assert!(!power.is_suspended().unwrap());
+    assert!(power.is_pwren_ready().unwrap());
+
+    sim.set_power_saving(true);
+    assert!(power.status().unwrap().power_saving_enabled);
+
+    assert!(power
+        .set_suspend_out_polarity(SuspendOutPolarity::Low)
+        .is_ok());
+    assert_eq!(
+        power.status().unwrap().suspend_out_polarity,
+        SuspendOutPolarity::Low
+    );
+    assert_eq!(sim.last_request(0x0B), Some(vec![1]));
+}
+
+#[test]
+fn power_suspend_levels() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+    let power = chip.power();
+
+    let levels = SuspendLevels::new()
+        .pin(Pin::Gpio0, Val::Low)
+        .pin(Pin::Gpio5, Val::High)
+        .pin(Pin::GpioA, Val::High)
+        .pin(Pin::GpioH, Val::Low)
+        .pin(Pin::Gpio0, Val::High);
+    assert_eq!(levels.level(Pin::Gpio0), Some(Val::High));
+    assert_eq!(levels.level(Pin::GpioH), Some(Val::Low));
+    assert_eq!(levels.level(Pin::Gpio1), None);
+    assert!(power.set_suspend_levels(&levels).is_ok());
+    // mask of pins in the low byte and their levels in the high byte
+    assert_eq!(sim.last_request(0x10), Some(vec![0x21, 0x21]));
+    assert_eq!(sim.last_request(0x11), Some(vec![0x81, 0x01]));
+
+    // no pin driven
+    assert!(power.set_suspend_levels(&SuspendLevels::default()).is_ok());
+    assert_eq!(sim.last_request(0x10), Some(vec![0, 0]));
+    assert_eq!(sim.last_request(0x11), Some(vec![0, 0]));
+}
+
+#[test]
+fn power_wakeup() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    let power = dev.power();
+    // WAKEUP is enabled at power-on
+    assert_eq!(
+        power.wakeup_config().unwrap(),
+        WakeupConfig {
+            wakeup_pin: true,
+            ri: None
+        }
+    );
+
+    assert!(power.disable_wakeup_pin().is_ok());
+    assert!(!power.wakeup_config().unwrap().wakeup_pin);
+    assert!(power.enable_wakeup_pin().is_ok());
+    assert!(power.enable_wakeup_pin().is_ok());
+    assert!(power.wakeup_config().unwrap().wakeup_pin);
+    assert_eq!(dev.gpio().owner(Group::Gpio_3), Some(Owner::Wakeup));
+
+    // configuration is kept by the device, not by the instance
+    assert!(dev.power().enable_ri_wakeup(RiWakeup::RisingEdge).is_ok());
+    assert_eq!(sim.last_request(0x07), Some(vec![1]));
+    assert_eq!(sim.last_request(0x0D), Some(vec![0]));
+    assert_eq!(sim.last_request(0x0C), Some(vec![1]));
+    assert_eq!(
+        dev.power().wakeup_config().unwrap().ri,
+        Some(RiWakeup::RisingEdge)
+    );
+    assert_eq!(dev.gpio().owner(Group::Gpio_4_5), Some(Owner::Wakeup));
+
+    // GPIO can't take pins used for wakeup
+    let gpio = dev.gpio();
+    assert!(matches!(
+        gpio.enable_pin(Group::Gpio_4_5),
+        Err(Ft260Error::PinConflict {
+            owner: Owner::Wakeup,
+            ..
+        })
+    ));
+    assert!(gpio.enable_pin(Group::Gpio_3).is_err());
+
+    assert!(power.disable_ri_wakeup().is_ok());
+    assert_eq!(sim.last_request(0x0C), Some(vec![0]));
+    assert_eq!(power.wakeup_config().unwrap().ri, None);
+    assert_eq!(dev.gpio().owner(Group::Gpio_4_5), None);
+
+    // and wakeup can't take pins used as GPIO
+    assert!(gpio.enable_pin(Group::Gpio_4_5).is_ok());
+    assert!(power.enable_ri_wakeup(RiWakeup::FallingEdge).is_err());
+    assert!(power.disable_ri_wakeup().is_err());
+
+    // pins are kept until wakeup is disabled
+    drop(power);
+    assert_eq!(dev.gpio().owner(Group::Gpio_3), Some(Owner::Wakeup));
+    assert!(dev.power().disable_wakeup_pin().is_ok());
+    assert_eq!(dev.gpio().owner(Group::Gpio_3), None);
+}
+
+#[test]
+fn power_wakeup_chip() {
+    let sim = Simulator::new();
+    let chip = sim_chip(&sim, 2).unwrap();
+
+    // interfaces of a chip share the configuration
+    let power = chip.device(1).unwrap().power();
+    assert!(power.enable_ri_wakeup(RiWakeup::FallingEdge).is_ok());
+    drop(power);
+    assert_eq!(
+        chip.power().wakeup_config().unwrap().ri,
+        Some(RiWakeup::FallingEdge)
+    );
+    assert_eq!(chip.gpio().owner(Group::Gpio_4_5), Some(Owner::Wakeup));
+    assert!(chip.power().disable_ri_wakeup().is_ok());
+    assert_eq!(chip.gpio().owner(Group::Gpio_4_5), None);
+}
@@ -0,0 +1,75 @@
+//!
+//! Tests of raw report API, running on `sim::Simulator`.
+//!
+use std::time::Duration;
+
+use ft260hid::chip::Clock;
+use ft260hid::device::{Device, ReportClass};
+use ft260hid::raw::{ReportId, Request};
+use ft260hid::sim::Simulator;
+use ft260hid::Ft260Error;
+
+#[test]
+fn raw_feature() {
+    let sim = Simulator::new();
+    let dev = Device::from_transport(sim.interface(0));
+    let raw = dev.raw();
+
+    assert!(raw.set_request(Request::SetClock, &[0]).is_ok());
+    assert_eq!(dev.clock().unwrap(), Clock::Mhz12);
+    assert_eq!(raw.get_feature(ReportId::FeatGpio).unwrap().len(), 4);
+    assert_eq!(
+        raw.get_feature(ReportId::FeatSystemSetting).unwrap()[1],
+        0 // clock
+    );
+
+    assert!(raw.set_feature(ReportId::FeatGpio, &[0; 4]).is_ok());
+    // GPIO report has fixed length
+    assert!(matches!(
+        raw.set_feature(ReportId::FeatGpio, &[0; 3]),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+    assert!(matches!(
+        raw.set_request(Request::SetClock, &[0; 63]),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+    // not feature reports
+    assert!(raw.get_feature(ReportId::OutI2cReadRequest).is_err());
+    assert!(raw.set_feature(ReportId::FeatChipCode, &[]).is_err());
+}
+
+#[test]
+fn raw_output_input() {
+    let sim = Simulator::new();
+    sim.set_uart_loopback(true);
+    let dev = Device::from_transport(sim.interface(1));
+    let _uart = dev.uart().init().unwrap();
+    let raw = dev.raw();
+
+    assert!(raw.take_input(ReportClass::Uart).is_empty());
+    assert!(raw
+        .write_output(ReportId::InOutUartReport04, &[3, b'a', b'b', b'c'])
+        .is_ok());
+    assert!(raw
+        .write_output(ReportId::InOutUartReport04, &[2, b'd', b'e'])
+        .is_ok());
+    let reports = raw.wait_input(ReportClass::Uart, Duration::from_millis(500));
+    let mut rx = Vec::new();
+    for rep in reports.iter() {
+        assert_eq!(rep.id, ReportId::InOutUartReport04);
+        let len = rep.payload[0] as usize;
+        rx.extend_from_slice(&rep.payload[1..1 + len]);
+    }
+    assert_eq!(rx, b"abcde");
+    assert!(raw.take_input(ReportClass::Uart).is_empty());
+
+    // 4 bytes at most in 0xF0
+    assert!(matches!(
+        raw.write_output(ReportId::InOutUartReport04, &[5, 1, 2, 3, 4, 5]),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+    assert!(raw
+        .write_output(ReportId::InOutUartReport08, &[5, 1, 2, 3, 4, 5])
+        .is_ok());
+    assert!(raw.write_output(ReportId::FeatGpio, &[0; 4]).is_err());
+}
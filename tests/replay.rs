@@ -0,0 +1,145 @@
+//!
+//! Tests of replaying sessions recorded on `sim::Simulator`.
+//!
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use ft260hid::capture::{self, Capture, Direction, Entry, Format};
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Dir, Group, Pin, Val};
+use ft260hid::io::{i2c, uart};
+use ft260hid::sim::{RegisterFile, Simulator};
+use ft260hid::transport::Replay;
+use ft260hid::Ft260Error;
+
+/// Writer collecting captured bytes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `session` on `dev` recording its reports, and return its result and the entries
+fn record<T>(dev: &Device, session: impl FnOnce(&Device) -> T) -> (T, Vec<Entry>) {
+    let captured = Captured::default();
+    let capture = Capture::new(captured.clone(), Format::Text).unwrap();
+    dev.start_capture(&capture, 0);
+    let res = session(dev);
+    dev.stop_capture();
+    let bytes = captured.0.lock().unwrap().clone();
+    (res, capture::read_text(&bytes[..]).unwrap())
+}
+
+fn i2c_gpio_session(dev: &Device) -> ([u8; 2], Val) {
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+    assert_eq!(
+        i2c.write(0x20, i2c::Flag::StartAndStop, &[0, 0x12, 0x34], 3)
+            .unwrap(),
+        3
+    );
+    let mut buf = [0u8; 2];
+    assert!(i2c
+        .write_read(0x20, &[0], 1, &mut buf, 2, i2c::DURATION_WAIT_DEFAULT)
+        .is_ok());
+    drop(i2c);
+
+    // read-modify-write of GPIO report
+    let gpio = dev.gpio();
+    assert!(gpio.enable_pin(Group::Gpio_B_C_D_E_F_H).is_ok());
+    assert!(gpio.set_dir(Pin::GpioB, Dir::Out).is_ok());
+    assert!(gpio.write(Pin::GpioB, Val::High).is_ok());
+    (buf, gpio.read(Pin::GpioB).unwrap())
+}
+
+#[test]
+fn replay_i2c_gpio() {
+    let sim = Simulator::new();
+    sim.attach_i2c(0x20, RegisterFile::new(16));
+    let dev = Device::from_transport(sim.interface(0));
+    let (recorded, entries) = record(&dev, i2c_gpio_session);
+    assert_eq!(recorded, ([0x12, 0x34], Val::High));
+    assert!(entries.iter().any(|e| e.data[0] == 0xC2));
+
+    let replay = Replay::from_entries(entries.clone(), 0);
+    let dev = Device::from_transport(replay.clone());
+    assert_eq!(i2c_gpio_session(&dev), recorded);
+    assert!(replay.is_finished());
+
+    // lines written are read back
+    for e in entries {
+        assert_eq!(Entry::from_line(&e.to_line()).unwrap(), Some(e));
+    }
+}
+
+#[test]
+fn replay_uart() {
+    let sim = Simulator::new();
+    sim.set_uart_loopback(true);
+    let dev = Device::from_transport(sim.interface(1));
+    let session = |dev: &Device| {
+        let uart = dev.uart().init().unwrap();
+        let data: Vec<u8> = (0..100).collect();
+        assert_eq!(uart.write(&data, data.len()).unwrap(), data.len());
+        let mut buf = [0u8; 100];
+        assert_eq!(
+            uart.read(&mut buf, 100, uart::DURATION_WAIT_DEFAULT)
+                .unwrap(),
+            100
+        );
+        buf
+    };
+    let (recorded, entries) = record(&dev, session);
+
+    let path = std::env::temp_dir().join(format!("ft260hid-replay-{}.log", std::process::id()));
+    std::fs::write(
+        &path,
+        entries
+            .iter()
+            .map(|e| e.to_line() + "\n")
+            .collect::<String>(),
+    )
+    .unwrap();
+    let replay = Replay::open(&path, 0).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let dev = Device::from_transport(replay.clone());
+    assert_eq!(session(&dev), recorded);
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_divergence() {
+    let sim = Simulator::new();
+    sim.attach_i2c(0x20, RegisterFile::new(16));
+    sim.attach_i2c(0x21, RegisterFile::new(16));
+    let dev = Device::from_transport(sim.interface(0));
+    let session = |dev: &Device, addr: u8| {
+        let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+        i2c.write(addr, i2c::Flag::StartAndStop, &[0, 1], 2)
+    };
+    let (_, entries) = record(&dev, |dev| session(dev, 0x20));
+
+    let replay = Replay::from_entries(entries, 0);
+    let dev = Device::from_transport(replay.clone());
+    assert!(matches!(
+        session(&dev, 0x21),
+        Err(Ft260Error::HidError { .. })
+    ));
+    let divergences = replay.divergences();
+    // recovery from the failure may diverge further
+    assert!(!divergences.is_empty());
+    assert_eq!(divergences[0].position, 3);
+    let expected = divergences[0].expected.as_ref().unwrap();
+    assert_eq!(expected.direction, Direction::ToDevice);
+    assert_eq!(expected.data[..2], [0xD0, 0x20]);
+    assert_eq!(divergences[0].actual.data[..2], [0xD0, 0x21]);
+    assert!(replay.remaining() > 0);
+    assert!(!replay.is_finished());
+}
@@ -0,0 +1,218 @@
+//!
+//! Tests of cases only `sim::Simulator` can set up, such as failures of I2C targets.
+//! The tests in `i2c.rs`, `uart.rs` and `gpio.rs` run on it too if no board is connected.
+//!
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Dir, Group, Owner, Pin, Val};
+use ft260hid::io::{i2c, uart, Initialized};
+use ft260hid::sim::{Eeprom, RegisterFile, Simulator};
+use ft260hid::{Ft260Error, I2cErrorKind};
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::common::EEPROM_ADDRESS;
+
+#[test]
+fn sim_i2c_errors() {
+    let sim = Simulator::new();
+    sim.attach_i2c(0x20, RegisterFile::new(16));
+    let dev = Device::from_transport(sim.interface(0));
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+    let mut buf = [0u8; 1];
+    let wait = i2c::DURATION_WAIT_DEFAULT;
+
+    assert!(matches!(
+        i2c.write_read(0x80, &[0], 1, &mut buf, 1, wait),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+    assert!(matches!(
+        i2c.write_read(0x20, &[0], 2, &mut buf, 1, wait),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+    assert!(matches!(
+        i2c.read(0x20, i2c::Flag::StartAndStop, &mut buf, 2, wait),
+        Err(Ft260Error::InvalidArgument { .. })
+    ));
+
+    match i2c.write_read(0x21, &[0], 1, &mut buf, 1, wait) {
+        Err(Ft260Error::I2cBusError {
+            kind,
+            report_id,
+            status,
+            address,
+            ..
+        }) => {
+            assert_eq!(kind, I2cErrorKind::AddressNack);
+            assert_eq!(report_id, 0xD0);
+            assert!(status.contains(i2c::BusStatus::AddressNack));
+            assert_eq!(address, 0x21);
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+    // register address out of range
+    match i2c.write_read(0x20, &[16], 1, &mut buf, 1, wait) {
+        Err(Ft260Error::I2cBusError { kind, .. }) => assert_eq!(kind, I2cErrorKind::DataNack),
+        res => panic!("unexpected result {:?}", res),
+    }
+    // the bus recovers
+    assert!(i2c.write_read(0x20, &[0], 1, &mut buf, 1, wait).is_ok());
+    assert_eq!(i2c.status().unwrap(), i2c::BusStatus::ControllerIdle);
+}
+
+#[test]
+fn sim_i2c_shared() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<i2c::I2c>();
+    assert_send_sync::<i2c::I2c<Initialized>>();
+    assert_send_sync::<uart::Uart>();
+    assert_send_sync::<uart::Uart<Initialized>>();
+    assert_send_sync::<ft260hid::io::gpio::Gpio>();
+
+    let sim = Simulator::new();
+    let regs = RegisterFile::new(16);
+    for reg in 0..16u8 {
+        regs.set(reg, reg ^ 0x5A);
+    }
+    sim.attach_i2c(0x20, regs);
+    // handles outlive the device they were created from
+    let i2c = Device::from_transport(sim.interface(0))
+        .i2c()
+        .init(i2c::KBPS_DEFAULT)
+        .unwrap();
+    let i2c = Arc::new(i2c);
+
+    let workers: Vec<_> = (0..4u8)
+        .map(|n| {
+            let i2c = i2c.clone();
+            thread::spawn(move || {
+                for i in 0..8u8 {
+                    let reg = (n * 4 + i) % 16;
+                    let mut buf = [0u8; 1];
+                    assert!(i2c
+                        .write_read(0x20, &[reg], 1, &mut buf, 1, i2c::DURATION_WAIT_DEFAULT)
+                        .is_ok());
+                    assert_eq!(buf[0], reg ^ 0x5A);
+                }
+            })
+        })
+        .collect();
+    for w in workers {
+        w.join().unwrap();
+    }
+}
+
+#[test]
+fn sim_uart_peer() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = Device::from_transport(sim.interface(1));
+    let uart = dev.uart().init().unwrap();
+
+    assert_eq!(uart.write(b"ping", 4).unwrap(), 4);
+    assert_eq!(peer.read(), b"ping");
+
+    peer.write(b"pong");
+    let mut buf = [0u8; 4];
+    assert_eq!(
+        uart.read(&mut buf, 4, uart::DURATION_WAIT_DEFAULT).unwrap(),
+        4
+    );
+    assert_eq!(&buf, b"pong");
+}
+
+#[test]
+fn sim_uart_read_wait() {
+    let sim = Simulator::new();
+    let peer = sim.uart_peer();
+    let dev = Device::from_transport(sim.interface(1));
+    let uart = dev.uart().init().unwrap();
+
+    // nothing arrives until the deadline
+    let mut buf = [0u8; 4];
+    let start = Instant::now();
+    assert_eq!(
+        uart.read(&mut buf, 4, Duration::from_millis(200)).unwrap(),
+        0
+    );
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    // the reader wakes up when data arrives
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        peer.write(b"late");
+    });
+    let start = Instant::now();
+    assert_eq!(
+        uart.read(&mut buf, 4, uart::DURATION_WAIT_DEFAULT).unwrap(),
+        4
+    );
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(&buf, b"late");
+    writer.join().unwrap();
+}
+
+#[test]
+fn sim_pin_ownership() {
+    let sim = Simulator::new();
+    sim.attach_i2c(EEPROM_ADDRESS, Eeprom::at24c02());
+    let dev = Device::from_transport(sim.interface(0));
+    let gpio = dev.gpio();
+
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+    assert_eq!(gpio.owner(Group::Gpio_0_1), Some(Owner::I2c));
+    let conflict = |res| {
+        matches!(
+            res,
+            Err(Ft260Error::PinConflict {
+                group: Group::Gpio_0_1,
+                owner: Owner::I2c
+            })
+        )
+    };
+    assert!(conflict(gpio.enable_pin(Group::Gpio_0_1)));
+    assert!(conflict(gpio.disable_pin(Group::Gpio_0_1)));
+    assert!(conflict(gpio.write(Pin::Gpio0, Val::Low)));
+    assert!(conflict(gpio.set_dir(Pin::Gpio1, Dir::Out)));
+    assert!(gpio.read(Pin::Gpio0).is_ok());
+    // I2C pins are intact
+    assert_eq!(
+        i2c.write(EEPROM_ADDRESS, i2c::Flag::StartAndStop, &[0, 1], 2)
+            .unwrap(),
+        2
+    );
+    drop(i2c);
+    assert_eq!(gpio.owner(Group::Gpio_0_1), None);
+
+    assert!(gpio.enable_pin(Group::Gpio_0_1).is_ok());
+    assert!(matches!(
+        dev.i2c().init(i2c::KBPS_DEFAULT),
+        Err(Ft260Error::PinConflict {
+            group: Group::Gpio_0_1,
+            owner: Owner::Gpio
+        })
+    ));
+    assert!(dev.gpio().enable_pin(Group::Gpio_0_1).is_err());
+    // another handle can't drive the pins owned by this one
+    let other = dev.gpio();
+    assert!(matches!(
+        other.write(Pin::Gpio0, Val::Low),
+        Err(Ft260Error::PinConflict {
+            group: Group::Gpio_0_1,
+            owner: Owner::Gpio
+        })
+    ));
+    assert!(gpio.write(Pin::Gpio0, Val::Low).is_ok());
+    drop(other);
+    assert_eq!(gpio.owner(Group::Gpio_0_1), Some(Owner::Gpio));
+    assert!(!dev.system_status().unwrap().i2c_enabled);
+    assert!(gpio.disable_pin(Group::Gpio_0_1).is_ok());
+    assert!(dev.i2c().init(i2c::KBPS_DEFAULT).is_ok());
+
+    // dropping the handle releases groups it enabled
+    assert!(gpio.enable_pin(Group::Gpio_B_C_D_E_F_H).is_ok());
+    assert!(dev.uart().init().is_err());
+    drop(gpio);
+    assert!(dev.uart().init().is_ok());
+}
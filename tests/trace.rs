@@ -0,0 +1,105 @@
+//!
+//! Tests of diagnostics through `tracing`, running on `sim::Simulator`.
+//!
+use ft260hid::device::Device;
+use ft260hid::io::{i2c, uart};
+use ft260hid::sim::{RegisterFile, Simulator};
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Writer collecting formatted output of the subscriber
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn trace_spans() {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .with_writer(captured.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let sim = Simulator::new();
+        sim.attach_i2c(0x20, RegisterFile::new(16));
+        let dev = Device::from_transport(sim.interface(0));
+        let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+        let mut buf = [0u8; 2];
+        assert!(i2c
+            .write_read(0x20, &[0], 1, &mut buf, 2, i2c::DURATION_WAIT_DEFAULT)
+            .is_ok());
+
+        let sim = Simulator::new();
+        sim.set_uart_loopback(true);
+        let dev = Device::from_transport(sim.interface(1));
+        let uart = dev.uart().init().unwrap();
+        assert_eq!(uart.write(b"abc", 3).unwrap(), 3);
+        let mut buf = [0u8; 3];
+        assert_eq!(
+            uart.read(&mut buf, 3, uart::DURATION_WAIT_DEFAULT).unwrap(),
+            3
+        );
+    });
+
+    let text = captured.text();
+    let in_span = |span: &str, message: &str| {
+        text.lines()
+            .any(|line| line.contains(span) && line.contains(message))
+    };
+    assert!(text.contains("initialized I2C"));
+    assert!(text.contains("initialized UART"));
+    // spans closed with the data report, bytes transferred and duration
+    assert!(in_span(
+        "i2c_write_read{addr=32 len_write=1 len_read=2 report_id=208 bytes=2 elapsed_us=",
+        "close"
+    ));
+    assert!(in_span(":read{report_id=208}: ft260hid::io::i2c", "close"));
+    assert!(in_span(
+        "uart_write{len=3 report_id=240 bytes=3 elapsed_us=",
+        "close"
+    ));
+    assert!(in_span(
+        "uart_read{len=3 report_id=240 bytes=3 elapsed_us=",
+        "close"
+    ));
+    // reports in the transfers
+    assert!(in_span("i2c_write_read{", "output report report_id=208"));
+    assert!(in_span("i2c_write_read{", "output report report_id=194"));
+    assert!(in_span(
+        "i2c_write_read{",
+        "input report payload report_id=208"
+    ));
+    assert!(in_span("uart_write{", "output report report_id=240"));
+    assert!(in_span("uart_read{", "input report payload report_id=240"));
+}
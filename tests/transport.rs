@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ft260hid::chip::Clock;
+use ft260hid::device::Device;
+use ft260hid::io::gpio::{Dir, Pin};
+use ft260hid::io::i2c;
+use ft260hid::transport::Transport;
+use ft260hid::{Ft260Error, Ft260Result};
+
+/// Transport double recording every report sent to it
+#[derive(Default, Clone)]
+struct MockTransport {
+    outputs: Arc<Mutex<Vec<Vec<u8>>>>,
+    features: Arc<Mutex<Vec<Vec<u8>>>>,
+    gpio: Arc<Mutex<[u8; 5]>>,
+    reads: Arc<Mutex<usize>>,
+    /// byte of System Status replaced by its position and value
+    status_patch: Arc<Mutex<Option<(usize, u8)>>>,
+}
+
+impl Transport for MockTransport {
+    fn read_timeout(&self, _buf: &mut [u8], timeout: i32) -> Ft260Result<usize> {
+        // no input report ever arrives
+        *self.reads.lock().unwrap() += 1;
+        thread::sleep(Duration::from_millis(timeout.max(0) as u64));
+        Ok(0)
+    }
+
+    fn write(&self, data: &[u8]) -> Ft260Result<usize> {
+        self.outputs.lock().unwrap().push(data.to_vec());
+        Ok(data.len())
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Ft260Result<usize> {
+        match buf[0] {
+            0xB0 => {
+                let gpio = self.gpio.lock().unwrap();
+                buf[..gpio.len()].copy_from_slice(&*gpio);
+                Ok(gpio.len())
+            }
+            // System Status in the default state with 48MHz clock
+            0xA1 => {
+                let mut status = [
+                    0xA1, 0x03, 0x02, 0x00, 0x01, 0x01, 0x04, 0x00, 0x01, 0x03, 0x06, 0x00, 0x01,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                ];
+                if let Some((pos, val)) = *self.status_patch.lock().unwrap() {
+                    status[pos] = val;
+                }
+                buf[..status.len()].copy_from_slice(&status);
+                Ok(status.len())
+            }
+            // I2C controller idle at 100 kbps
+            0xC0 => {
+                buf[1..4].copy_from_slice(&[0x20, 100, 0]);
+                Ok(4)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn send_feature_report(&self, data: &[u8]) -> Ft260Result<()> {
+        if data[0] == 0xB0 {
+            self.gpio.lock().unwrap().copy_from_slice(&data[..5]);
+        }
+        self.features.lock().unwrap().push(data.to_vec());
+        Ok(())
+    }
+}
+
+#[test]
+fn i2c_write_output_report() {
+    let mock = MockTransport::default();
+    let dev = Device::from_transport(mock.clone());
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+
+    // I2C speed request to 0xA1
+    let features = mock.features.lock().unwrap().clone();
+    assert_eq!(&features.last().unwrap()[..4], &[0xA1, 0x22, 100, 0]);
+
+    let data = [0x12u8, 0x34, 0x56];
+    assert_eq!(
+        i2c.write(0x50, i2c::Flag::StartAndStop, &data, data.len())
+            .unwrap(),
+        data.len()
+    );
+    let outputs = mock.outputs.lock().unwrap().clone();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(&outputs[0][..7], &[0xD0, 0x50, 0x06, 3, 0x12, 0x34, 0x56]);
+}
+
+#[test]
+fn i2c_init_unknown_status() {
+    let mock = MockTransport::default();
+    // GPIOA function unknown to the library
+    *mock.status_patch.lock().unwrap() = Some((9, 0xFF));
+    let dev = Device::from_transport(mock.clone());
+    assert!(dev.system_status().is_err());
+    assert_eq!(dev.clock().unwrap(), Clock::Mhz48);
+    assert!(dev.i2c().init(i2c::KBPS_DEFAULT).is_ok());
+    assert!(dev.uart().init().is_ok());
+}
+
+#[test]
+fn i2c_write_read_timeout() {
+    let mock = MockTransport::default();
+    let dev = Device::from_transport(mock.clone());
+    let i2c = dev.i2c().init(i2c::KBPS_DEFAULT).unwrap();
+
+    // the read request is sent but no data comes back
+    let mut buf = [0u8; 4];
+    match i2c.write_read(0x50, &[0], 1, &mut buf, 4, Duration::from_millis(200)) {
+        Err(Ft260Error::Timeout {
+            report_id,
+            address,
+            transferred,
+            requested,
+        }) => {
+            assert_eq!(report_id, 0xD0);
+            assert_eq!(address, Some(0x50));
+            assert_eq!(transferred, 0);
+            assert_eq!(requested, 4);
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+    let outputs = mock.outputs.lock().unwrap().clone();
+    assert_eq!(&outputs.last().unwrap()[..5], &[0xC2, 0x50, 0x07, 4, 0]);
+}
+
+#[test]
+fn gpio_read_modify_write() {
+    let mock = MockTransport::default();
+    *mock.gpio.lock().unwrap() = [0xB0, 0x00, 0x01, 0x00, 0x00];
+    let dev = Device::from_transport(mock.clone());
+    let gpio = dev.gpio();
+    assert!(gpio.set_dir(Pin::Gpio2, Dir::Out).is_ok());
+    assert!(gpio.set_dir(Pin::GpioB, Dir::Out).is_ok());
+    assert_eq!(*mock.gpio.lock().unwrap(), [0xB0, 0x00, 0x05, 0x00, 0x02]);
+}
+
+#[test]
+fn reader_blocks_and_stops() {
+    let mock = MockTransport::default();
+    let dev = Device::from_transport(mock.clone());
+    thread::sleep(Duration::from_millis(300));
+    // the reading thread waits in `read_timeout` instead of spinning
+    assert!(*mock.reads.lock().unwrap() < 20);
+    let start = Instant::now();
+    drop(dev);
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
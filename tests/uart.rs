@@ -1,19 +1,19 @@
 use std::thread;
 use std::time::Duration;
 
-use ft260hid::device;
 use ft260hid::io::uart;
 
 use rand::prelude::*;
 use serial_test::serial;
 
+use crate::common::Board;
+
 #[test]
 #[serial]
 fn test_uart_cfg() {
-    let dev = device::open(1).unwrap();
-    let mut uart = dev.uart();
-
-    assert!(uart.init().is_ok());
+    let board = Board::open();
+    let dev = &board.uart;
+    let uart = dev.uart().init().unwrap();
     let cfg = uart::Config::default();
     assert!(uart.set_config(&cfg).is_ok());
     let res = uart.get_config();
@@ -41,10 +41,9 @@ fn test_uart_cfg() {
 fn test_uart_tx_rx() {
     let mut buf_tx = [0u8; 256];
 
-    let dev = device::open(1).unwrap();
-    let mut uart = dev.uart();
-
-    assert!(uart.init().is_ok());
+    let board = Board::open();
+    let dev = &board.uart;
+    let uart = dev.uart().init().unwrap();
     assert!(uart.set_config(&uart::Config::default()).is_ok());
 
     let len_list = [8usize, 32, 128, 256];
@@ -75,3 +74,24 @@ fn test_uart_tx_rx() {
         }
     }
 }
+
+#[test]
+#[serial]
+fn test_uart_moved() {
+    let board = Board::open();
+    let uart = board.uart.uart().init().unwrap();
+    assert!(uart.set_config(&uart::Config::default()).is_ok());
+    drop(board);
+
+    // the handle outlives the device it was created from
+    let echo = thread::spawn(move || {
+        assert_eq!(uart.write(b"moved", 5).unwrap(), 5);
+        let mut buf = [0u8; 5];
+        assert_eq!(
+            uart.read(&mut buf, 5, uart::DURATION_WAIT_DEFAULT).unwrap(),
+            5
+        );
+        buf
+    });
+    assert_eq!(&echo.join().unwrap(), b"moved");
+}